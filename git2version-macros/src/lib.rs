@@ -0,0 +1,142 @@
+//! The [git2version-macros](https://crates.io/crates/git2version-macros) crate provides the [`git_info!`] macro,
+//! which gets the version of the package from git without needing the proxy crate that [git2version](https://crates.io/crates/git2version) requires.
+//!
+//!
+//! ## Setup
+//!
+//! Add both crates to your Cargo.toml. `git2version` provides the types that the macro generates.
+//!
+//! ```toml
+//! [dependencies]
+//! git2version = "*"
+//! git2version-macros = "*"
+//! ```
+//!
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! pub const GITINFO: Option<git2version::GitInfo> = git2version_macros::git_info!();
+//!
+//! fn main() {
+//!     println!("Version from git: {:?}", GITINFO);
+//! }
+//! ```
+//!
+//!
+//! ## Incremental compilation
+//!
+//! Proc macros can't use `cargo:rerun-if-changed` like the `build.rs` of a proxy crate can. Instead, [`git_info!`] includes
+//! some files from the `.git` directory with `include_bytes!`, so that cargo recompiles your crate when they change. This means:
+//!
+//! * New commits, checking out a different commit and staging changes are noticed, because they change `.git/HEAD`,
+//!   the current branch's ref or `.git/index`.
+//! * Changes to the source files of the crate calling [`git_info!`] are noticed, because the crate is recompiled anyway.
+//! * Other changes are **not** noticed until one of the above happens. In particular, the `modified` flag can be stale
+//!   after unstaged changes to files outside of the crate, and new tags are only picked up once they're packed or you commit.
+//!   The first commit in a new repository and a git repository being created around your crate also aren't noticed.
+//!   Run `cargo clean -p <your crate>` to force an update.
+//!
+//! If you need reliable version information, use the proxy crate setup described in the `git2version` documentation instead.
+//! It reruns on any change in the repository without recompiling your main crate. It also supports the `GIT2VERSION_*` environment
+//! variables, snapshot files and the `ProxyBuildConfig` options, which [`git_info!`] doesn't.
+
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+
+use git2version::git2::Repository;
+use git2version::{GitInfoOwned, get_git_info};
+use proc_macro::TokenStream;
+use std::path::{Path, PathBuf};
+
+/// Evaluates to the git version information of the crate calling it, as an `Option<git2version::GitInfo<'static, 'static>>`.
+///
+/// The repository is discovered from the directory containing the calling crate's `Cargo.toml`. The result is `None` if there
+/// is no git repository or if there was an error looking up the version information, just like the `GITINFO` constant of a proxy crate.
+///
+/// The macro can be used in `const` contexts. See the [crate documentation](crate#incremental-compilation) for when cargo
+/// re-evaluates it.
+///
+/// # Example
+///
+/// ```ignore
+/// pub const GITINFO: Option<git2version::GitInfo> = git2version_macros::git_info!();
+/// ```
+#[proc_macro]
+pub fn git_info(input: TokenStream) -> TokenStream {
+    if !input.is_empty() {
+        return compile_error("git_info!() doesn't take any arguments");
+    }
+    let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") else {
+        return compile_error(
+            "CARGO_MANIFEST_DIR isn't set, git_info!() must be compiled by cargo",
+        );
+    };
+    let (git_info, tracked_files) = match Repository::discover(&manifest_dir) {
+        Ok(repo) => (get_git_info(&repo).ok(), tracked_files(&repo)),
+        Err(_) => (None, Vec::new()),
+    };
+
+    let mut code = String::from("{\n");
+    for file in tracked_files {
+        let Some(file) = file.to_str() else {
+            return compile_error(&format!(
+                "git_info!() doesn't support git repositories whose path isn't valid UTF-8: {}",
+                file.display()
+            ));
+        };
+        // Make cargo recompile the calling crate if the file changes
+        code += &format!("const _: &[u8] = ::core::include_bytes!({file:?});\n");
+    }
+    code += &git_info_expr(git_info.as_ref());
+    code += "\n}";
+    code.parse().unwrap()
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("::core::compile_error!({message:?})")
+        .parse()
+        .unwrap()
+}
+
+/// Files in the `.git` directory whose changes should cause [`git_info!`] to be re-evaluated.
+fn tracked_files(repo: &Repository) -> Vec<PathBuf> {
+    let git_dir = repo.path();
+    let common_dir = repo.commondir();
+    let mut files = vec![git_dir.join("HEAD"), git_dir.join("index")];
+    // Track the branch HEAD points to, so that we notice new commits on it. Refs can be loose files or in `packed-refs`.
+    if let Ok(head) = repo.find_reference("HEAD")
+        && let Some(target) = head.symbolic_target()
+    {
+        files.push(common_dir.join(target));
+    }
+    files.push(common_dir.join("packed-refs"));
+    files.retain(|file| file.is_file());
+    files.iter().map(|file| absolute(file)).collect()
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn git_info_expr(git_info: Option<&GitInfoOwned>) -> String {
+    let Some(git_info) = git_info else {
+        return "::core::option::Option::<::git2version::GitInfo<'static, 'static>>::None"
+            .to_string();
+    };
+    let tag_info = match &git_info.tag_info {
+        Some(tag_info) => format!(
+            "::core::option::Option::Some(::git2version::TagInfo {{ tag: {:?}, commits_since_tag: {} }})",
+            tag_info.tag, tag_info.commits_since_tag,
+        ),
+        None => "::core::option::Option::None".to_string(),
+    };
+    let worktree = match &git_info.worktree {
+        Some(worktree) => format!("::core::option::Option::Some({worktree:?})"),
+        None => "::core::option::Option::None".to_string(),
+    };
+    format!(
+        "::core::option::Option::<::git2version::GitInfo<'static, 'static>>::Some(::git2version::GitInfo {{ tag_info: {tag_info}, commit_id: {:?}, modified: {}, source: ::git2version::VersionSource::{:?}, worktree: {worktree} }})",
+        git_info.commit_id, git_info.modified, git_info.source,
+    )
+}
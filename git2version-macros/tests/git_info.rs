@@ -0,0 +1,205 @@
+use git2::Repository;
+use std::path::Path;
+use std::process::Command;
+use tempdir::TempDir;
+
+use git2version::{COMMIT_ID_SHORT_HASH_LENGTH, GitInfo, TagInfo, VersionSource};
+
+const FILENAME: &str = "some_file";
+
+fn create_repo(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+    repo.config()
+        .unwrap()
+        .set_str("user.name", "Test User")
+        .unwrap();
+    repo.config()
+        .unwrap()
+        .set_str("user.email", "test@example.com")
+        .unwrap();
+    repo
+}
+
+fn create_change_and_commit(repo: &Repository, content: &str) {
+    std::fs::write(repo.workdir().unwrap().join(FILENAME), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(FILENAME)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &sig, &sig, content, &tree, &parents)
+        .unwrap();
+}
+
+fn create_tag(repo: &Repository, tag: &str) {
+    let head_commit = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+    repo.tag_lightweight(tag, &head_commit, true).unwrap();
+}
+
+fn head_commit_id(repo: &Repository) -> String {
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    head.id().to_string()[..COMMIT_ID_SHORT_HASH_LENGTH].to_string()
+}
+
+fn make_test_project(main_rs: &str) -> TempDir {
+    let dir = TempDir::new("git2version-macros-test").unwrap();
+    let path_to_macros_crate = env!("CARGO_MANIFEST_DIR");
+    let path_to_git2version_crate = Path::new(path_to_macros_crate).parent().unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        format!(
+            r#"
+[package]
+name = "git2version-macros-test"
+edition = "2021"
+version = "0.1.0"
+
+[workspace]
+
+[dependencies]
+git2version = {{path = "{}", features=["serde"]}}
+git2version-macros = {{path = "{path_to_macros_crate}"}}
+serde_json = "^1.0.96"
+            "#,
+            path_to_git2version_crate.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join(".gitignore"), "target/\nCargo.lock\n").unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), main_rs).unwrap();
+    dir
+}
+
+const PRINT_GITINFO_MAIN_RS: &str = r#"
+const GITINFO: Option<git2version::GitInfo> = git2version_macros::git_info!();
+
+fn main() {
+    println!("{}", serde_json::to_string(&GITINFO).unwrap());
+}
+"#;
+
+fn run_test_project(project_dir: &Path) -> String {
+    let output = Command::new(env!("CARGO"))
+        .arg("run")
+        .current_dir(project_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "cargo run failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn assert_version(project_dir: &Path, expected_version: Option<GitInfo>) {
+    let output = run_test_project(project_dir);
+    let actual_version: Option<GitInfo> = serde_json::from_str(&output).unwrap();
+    assert_eq!(expected_version, actual_version);
+}
+
+#[test]
+fn no_git() {
+    let project_dir = make_test_project(PRINT_GITINFO_MAIN_RS);
+    assert_version(project_dir.path(), None);
+}
+
+#[test]
+fn with_tag() {
+    let project_dir = make_test_project(PRINT_GITINFO_MAIN_RS);
+    let repo = create_repo(project_dir.path());
+    create_change_and_commit(&repo, "1");
+    create_tag(&repo, "v1.2.3-alpha");
+    create_change_and_commit(&repo, "2");
+    create_change_and_commit(&repo, "3");
+    assert_version(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3-alpha",
+                commits_since_tag: 2,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn new_commit_is_noticed() {
+    let project_dir = make_test_project(PRINT_GITINFO_MAIN_RS);
+    let repo = create_repo(project_dir.path());
+    create_change_and_commit(&repo, "1");
+    create_tag(&repo, "v1.0.0");
+    assert_version(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.0.0",
+                commits_since_tag: 0,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+
+    create_change_and_commit(&repo, "2");
+    assert_version(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.0.0",
+                commits_since_tag: 1,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn staged_change_is_noticed() {
+    let project_dir = make_test_project(PRINT_GITINFO_MAIN_RS);
+    let repo = create_repo(project_dir.path());
+    create_change_and_commit(&repo, "1");
+    let unmodified = run_test_project(project_dir.path());
+    assert!(unmodified.contains(r#""modified":false"#), "{unmodified}");
+
+    std::fs::write(project_dir.path().join(FILENAME), "2").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(FILENAME)).unwrap();
+    index.write().unwrap();
+    let modified = run_test_project(project_dir.path());
+    assert!(modified.contains(r#""modified":true"#), "{modified}");
+}
+
+#[test]
+fn arguments_are_rejected() {
+    let project_dir = make_test_project(
+        r#"
+const GITINFO: Option<git2version::GitInfo> = git2version_macros::git_info!(".");
+
+fn main() {}
+"#,
+    );
+    let output = Command::new(env!("CARGO"))
+        .arg("build")
+        .current_dir(project_dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("git_info!() doesn't take any arguments"),
+        "{stderr}"
+    );
+}
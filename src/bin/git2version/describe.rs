@@ -0,0 +1,271 @@
+use clap::{Args, ValueEnum};
+use git2version::{OnVersionMismatch, ProxyBuildConfig, ProxyBuildResult};
+use std::path::PathBuf;
+
+use crate::read::shell_quote;
+
+#[derive(Args, Debug)]
+pub struct DescribeArgs {
+    /// Directory of the proxy crate, or any other directory in the git repository
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Name of the proxy, as passed to `init_proxy_build!`. Selects the `GIT2VERSION_<NAME>__*` environment variable overrides.
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Read the version information from the git repository containing this directory instead of the one containing PATH.
+    /// Relative paths are relative to PATH.
+    #[arg(long, value_name = "DIR")]
+    repository_dir: Option<PathBuf>,
+
+    /// Stop searching for the git repository at this directory, like `GIT_CEILING_DIRECTORIES`. Can be given several times.
+    /// Relative paths are relative to PATH.
+    #[arg(long, value_name = "DIR")]
+    ceiling_dir: Vec<PathBuf>,
+
+    /// Ignore a git repository that doesn't track any files in PATH (or --repository-dir)
+    #[arg(long)]
+    require_tracked: bool,
+
+    /// Fall back to the package version from the `Cargo.toml` in this directory if there is no version information from git.
+    /// Relative paths are relative to PATH.
+    #[arg(long, value_name = "CRATE_DIR")]
+    cargo_manifest_fallback: Option<PathBuf>,
+
+    /// Check that the git tag matches the package version from the `Cargo.toml` in this directory.
+    /// Relative paths are relative to PATH.
+    #[arg(long, value_name = "CRATE_DIR")]
+    cargo_version_check: Option<PathBuf>,
+
+    /// What to do if the cargo version check fails [default: fail]
+    #[arg(long, value_enum, requires = "cargo_version_check")]
+    on_version_mismatch: Option<MismatchAction>,
+
+    /// Prefix that is stripped from the tag before comparing it with the `Cargo.toml` version
+    #[arg(long, requires = "cargo_version_check")]
+    cargo_version_check_tag_prefix: Option<String>,
+
+    /// Template for the version string, see `GitInfo::format_with` for the syntax
+    #[arg(long)]
+    version_string_template: Option<String>,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Display)]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MismatchAction {
+    /// Print a warning but continue
+    Warn,
+
+    /// Fail with an error
+    Fail,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// The `VERSION_STRING` constant that `init_proxy_lib!` would generate
+    Display,
+
+    /// The version information as JSON, `null` if it isn't known
+    Json,
+
+    /// `KEY='value'` lines with the values of the constants that `init_proxy_lib!` would generate.
+    /// The values are quoted, so the output can be evaluated by a shell.
+    Env,
+}
+
+pub fn run(args: DescribeArgs) -> Result<(), String> {
+    let path = args
+        .path
+        .to_str()
+        .ok_or_else(|| format!("{} isn't valid UTF-8", args.path.display()))?;
+    let output = git2version::compute_proxy_build(path, args.name.as_deref(), &config(&args));
+    for warning in &output.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    print!("{}", format_result(&output.result?, args.format));
+    Ok(())
+}
+
+fn config(args: &DescribeArgs) -> ProxyBuildConfig {
+    let mut config = ProxyBuildConfig::new().require_tracked(args.require_tracked);
+    if let Some(repository_dir) = &args.repository_dir {
+        config = config.repository_dir(repository_dir);
+    }
+    for ceiling_dir in &args.ceiling_dir {
+        config = config.ceiling_dir(ceiling_dir);
+    }
+    if let Some(crate_dir) = &args.cargo_manifest_fallback {
+        config = config.cargo_manifest_fallback(crate_dir);
+    }
+    if let Some(crate_dir) = &args.cargo_version_check {
+        let on_mismatch = match args.on_version_mismatch {
+            Some(MismatchAction::Warn) => OnVersionMismatch::Warn,
+            Some(MismatchAction::Fail) | None => OnVersionMismatch::Fail,
+        };
+        config = config.cargo_version_check(crate_dir, on_mismatch);
+        if let Some(tag_prefix) = &args.cargo_version_check_tag_prefix {
+            config = config.cargo_version_check_tag_prefix(tag_prefix);
+        }
+    }
+    if let Some(template) = &args.version_string_template {
+        config = config.version_string_template(template);
+    }
+    config
+}
+
+fn format_result(result: &ProxyBuildResult, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Display => format!("{}\n", result.version_string),
+        OutputFormat::Json => {
+            let mut json = serde_json::to_string_pretty(&result.repository_version)
+                .expect("Serializing to JSON can't fail");
+            json.push('\n');
+            json
+        }
+        OutputFormat::Env => format_env(result),
+    }
+}
+
+fn format_env(result: &ProxyBuildResult) -> String {
+    let git_info = result.repository_version.as_ref();
+    let tag_info = git_info.and_then(|git_info| git_info.tag_info.as_ref());
+    let lines = [
+        ("VERSION", result.version.clone()),
+        ("VERSION_STRING", result.version_string.clone()),
+        ("IS_KNOWN", git_info.is_some().to_string()),
+        (
+            "TAG",
+            tag_info
+                .map(|tag_info| tag_info.tag.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "COMMITS_SINCE_TAG",
+            tag_info
+                .map(|tag_info| tag_info.commits_since_tag.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "COMMIT_ID",
+            git_info
+                .map(|git_info| git_info.commit_id.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "MODIFIED",
+            git_info
+                .map(|git_info| git_info.modified.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "SOURCE",
+            git_info
+                .map(|git_info| git_info.source.name().to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "WORKTREE",
+            git_info
+                .and_then(|git_info| git_info.worktree.clone())
+                .unwrap_or_default(),
+        ),
+    ];
+    lines
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}\n", shell_quote(&value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2version::{GitInfoOwned, TagInfoOwned, VersionSource};
+
+    fn result_with_tag() -> ProxyBuildResult {
+        ProxyBuildResult {
+            repository_version: Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v1.2.3".to_string(),
+                    commits_since_tag: 5,
+                }),
+                commit_id: "abcdef1234".to_string(),
+                modified: true,
+                source: VersionSource::Git,
+                worktree: Some("feature".to_string()),
+            }),
+            version: "v1.2.3+5.gabcdef1234.modified".to_string(),
+            long_version: String::new(),
+            version_string: "1.2.3-5".to_string(),
+            submodules: Vec::new(),
+            additional_repositories: Vec::new(),
+        }
+    }
+
+    fn result_unknown() -> ProxyBuildResult {
+        ProxyBuildResult {
+            repository_version: None,
+            version: "unknown".to_string(),
+            long_version: "unknown".to_string(),
+            version_string: "unknown".to_string(),
+            submodules: Vec::new(),
+            additional_repositories: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            "1.2.3-5\n",
+            format_result(&result_with_tag(), OutputFormat::Display)
+        );
+        assert_eq!(
+            "unknown\n",
+            format_result(&result_unknown(), OutputFormat::Display)
+        );
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        for result in [result_with_tag(), result_unknown()] {
+            let json = format_result(&result, OutputFormat::Json);
+            assert_eq!(
+                result.repository_version,
+                serde_json::from_str::<Option<GitInfoOwned>>(&json).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn env_with_tag() {
+        assert_eq!(
+            "VERSION='v1.2.3+5.gabcdef1234.modified'\nVERSION_STRING='1.2.3-5'\nIS_KNOWN='true'\nTAG='v1.2.3'\nCOMMITS_SINCE_TAG='5'\nCOMMIT_ID='abcdef1234'\nMODIFIED='true'\nSOURCE='git'\nWORKTREE='feature'\n",
+            format_result(&result_with_tag(), OutputFormat::Env)
+        );
+    }
+
+    #[test]
+    fn env_unknown() {
+        assert_eq!(
+            "VERSION='unknown'\nVERSION_STRING='unknown'\nIS_KNOWN='false'\nTAG=''\nCOMMITS_SINCE_TAG=''\nCOMMIT_ID=''\nMODIFIED=''\nSOURCE=''\nWORKTREE=''\n",
+            format_result(&result_unknown(), OutputFormat::Env)
+        );
+    }
+
+    #[test]
+    fn env_quotes_values() {
+        let mut result = result_with_tag();
+        result.version_string = "it's $(touch pwned)\nsecond line".to_string();
+        let repository_version = result.repository_version.as_mut().unwrap();
+        repository_version.tag_info.as_mut().unwrap().tag = "v1 \"beta\"".to_string();
+        let env = format_result(&result, OutputFormat::Env);
+        assert!(env.contains("TAG='v1 \"beta\"'\n"), "{env}");
+        assert!(
+            env.contains("VERSION_STRING='it'\\''s $(touch pwned)\nsecond line'\n"),
+            "{env}"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use clap::Args;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table};
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Path to the `Cargo.toml` of the crate that should use the version proxy
+    #[arg(long, default_value = "Cargo.toml")]
+    manifest_path: PathBuf,
+
+    /// Name of the proxy crate. It is created in a directory with this name next to the `Cargo.toml`.
+    #[arg(long, default_value = "version_proxy")]
+    name: String,
+}
+
+pub fn run(args: InitArgs) -> Result<(), String> {
+    for action in init(&args.manifest_path, &args.name)? {
+        println!("{action}");
+    }
+    Ok(())
+}
+
+/// Sets up the proxy crate `name` for the crate with the manifest at `manifest_path` and returns what was done.
+///
+/// Running this again on an already set up crate doesn't change anything.
+fn init(manifest_path: &Path, name: &str) -> Result<Vec<String>, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("'{name}' isn't a valid crate name"));
+    }
+    let manifest_path = std::fs::canonicalize(manifest_path)
+        .map_err(|err| format!("Error reading {}: {err}", manifest_path.display()))?;
+    let crate_dir = manifest_path
+        .parent()
+        .expect("Canonicalized file path must have a parent");
+    let mut manifest = read_manifest(&manifest_path)?;
+    if !manifest.contains_table("package") {
+        return Err(format!(
+            "{} doesn't have a [package] section. Use --manifest-path to select the crate that should use the version proxy.",
+            manifest_path.display()
+        ));
+    }
+
+    let mut actions = Vec::new();
+    let proxy_dir = crate_dir.join(name);
+    for (file_name, content) in proxy_files(name) {
+        let path = proxy_dir.join(file_name);
+        if path.exists() {
+            actions.push(format!("{} already exists", path.display()));
+        } else {
+            write_file(&path, &content)?;
+            actions.push(format!("Created {}", path.display()));
+        }
+    }
+
+    let dependencies = table_mut(&mut manifest, "dependencies")?;
+    if dependencies.contains_key(name) {
+        actions.push(format!(
+            "{} already depends on {name}",
+            manifest_path.display()
+        ));
+    } else {
+        let mut dependency = InlineTable::new();
+        dependency.insert("path", format!("./{name}").into());
+        dependencies.insert(name, toml_edit::value(dependency));
+        actions.push(format!(
+            "Added dependency on {name} to {}",
+            manifest_path.display()
+        ));
+    }
+
+    match find_workspace_root(crate_dir)? {
+        Some(workspace_manifest_path) if workspace_manifest_path == manifest_path => {
+            let action = add_workspace_member(&mut manifest, &manifest_path, name)?;
+            actions.push(action);
+            write_file(&manifest_path, &manifest.to_string())?;
+        }
+        Some(workspace_manifest_path) => {
+            write_file(&manifest_path, &manifest.to_string())?;
+            let workspace_dir = workspace_manifest_path
+                .parent()
+                .expect("Canonicalized file path must have a parent");
+            let member = relative_path(workspace_dir, &proxy_dir);
+            let mut workspace_manifest = read_manifest(&workspace_manifest_path)?;
+            let action =
+                add_workspace_member(&mut workspace_manifest, &workspace_manifest_path, &member)?;
+            actions.push(action);
+            write_file(&workspace_manifest_path, &workspace_manifest.to_string())?;
+        }
+        None => {
+            // Like in the setup instructions, make the crate the root of a workspace containing the proxy crate
+            manifest.insert("workspace", Item::Table(Table::new()));
+            let action = add_workspace_member(&mut manifest, &manifest_path, name)?;
+            actions.push(action);
+            write_file(&manifest_path, &manifest.to_string())?;
+        }
+    }
+
+    Ok(actions)
+}
+
+fn proxy_files(name: &str) -> [(&'static str, String); 3] {
+    let version = env!("CARGO_PKG_VERSION");
+    [
+        (
+            "Cargo.toml",
+            format!(
+                r#"[package]
+name = "{name}"
+# The version field here is ignored, no need to change it
+version = "0.0.0"
+edition = "2021"
+
+[dependencies]
+git2version = "^{version}"
+
+[build-dependencies]
+git2version = {{ version = "^{version}", features = ["build"] }}
+"#
+            ),
+        ),
+        (
+            "build.rs",
+            "fn main() {\n    git2version::init_proxy_build!();\n}\n".to_string(),
+        ),
+        (
+            "src/lib.rs",
+            "git2version::init_proxy_lib!();\n".to_string(),
+        ),
+    ]
+}
+
+/// Finds the `Cargo.toml` with a `[workspace]` section in `crate_dir` or its parent directories.
+fn find_workspace_root(crate_dir: &Path) -> Result<Option<PathBuf>, String> {
+    for dir in crate_dir.ancestors() {
+        let manifest_path = dir.join("Cargo.toml");
+        if manifest_path.is_file() && read_manifest(&manifest_path)?.contains_key("workspace") {
+            return Ok(Some(manifest_path));
+        }
+    }
+    Ok(None)
+}
+
+fn add_workspace_member(
+    manifest: &mut DocumentMut,
+    manifest_path: &Path,
+    member: &str,
+) -> Result<String, String> {
+    let workspace = table_mut(manifest, "workspace")?;
+    let members = workspace
+        .entry("members")
+        .or_insert_with(|| toml_edit::value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| {
+            format!(
+                "workspace.members in {} isn't an array",
+                manifest_path.display()
+            )
+        })?;
+    let normalize = |member: &str| {
+        member
+            .trim_start_matches("./")
+            .trim_end_matches('/')
+            .to_string()
+    };
+    if members
+        .iter()
+        .any(|existing| existing.as_str().map(normalize) == Some(normalize(member)))
+    {
+        Ok(format!(
+            "{member} already is a workspace member in {}",
+            manifest_path.display()
+        ))
+    } else {
+        members.push(member);
+        Ok(format!(
+            "Added {member} to the workspace members in {}",
+            manifest_path.display()
+        ))
+    }
+}
+
+fn table_mut<'a>(manifest: &'a mut DocumentMut, key: &str) -> Result<&'a mut Table, String> {
+    manifest
+        .entry(key)
+        .or_insert_with(toml_edit::table)
+        .as_table_mut()
+        .ok_or_else(|| format!("[{key}] isn't a table"))
+}
+
+fn relative_path(base: &Path, path: &Path) -> String {
+    let relative = path
+        .strip_prefix(base)
+        .expect("Workspace root is an ancestor of the crate directory");
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn read_manifest(path: &Path) -> Result<DocumentMut, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Error reading {}: {err}", path.display()))?;
+    content
+        .parse()
+        .map_err(|err| format!("Error parsing {}: {err}", path.display()))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| format!("Error creating {}: {err}", parent.display()))?;
+    }
+    std::fs::write(path, content).map_err(|err| format!("Error writing {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    const CRATE_MANIFEST: &str = r#"[package]
+name = "my-app"
+version = "1.0.0"
+
+[dependencies]
+# Some comment that should be kept
+serde = "1"
+"#;
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn standalone_crate() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, CRATE_MANIFEST).unwrap();
+
+        init(&manifest_path, "version_proxy").unwrap();
+
+        assert_eq!(
+            format!(
+                "{CRATE_MANIFEST}version_proxy = {{ path = \"./version_proxy\" }}\n\n[workspace]\nmembers = [\"version_proxy\"]\n"
+            ),
+            read(&manifest_path)
+        );
+        let proxy_dir = dir.path().join("version_proxy");
+        assert!(read(&proxy_dir.join("Cargo.toml")).contains("name = \"version_proxy\""));
+        assert_eq!(
+            "fn main() {\n    git2version::init_proxy_build!();\n}\n",
+            read(&proxy_dir.join("build.rs"))
+        );
+        assert_eq!(
+            "git2version::init_proxy_lib!();\n",
+            read(&proxy_dir.join("src/lib.rs"))
+        );
+    }
+
+    #[test]
+    fn existing_workspace_in_crate() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(
+            &manifest_path,
+            format!("{CRATE_MANIFEST}\n[workspace]\nmembers = [\"other\"]\n"),
+        )
+        .unwrap();
+
+        init(&manifest_path, "version_proxy").unwrap();
+
+        assert_eq!(
+            format!(
+                "{CRATE_MANIFEST}version_proxy = {{ path = \"./version_proxy\" }}\n\n[workspace]\nmembers = [\"other\", \"version_proxy\"]\n"
+            ),
+            read(&manifest_path)
+        );
+    }
+
+    #[test]
+    fn existing_workspace_in_parent() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let workspace_manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            "[workspace]\nmembers = [\"crates/my-app\"]\n",
+        )
+        .unwrap();
+        let manifest_path = dir.path().join("crates/my-app/Cargo.toml");
+        write_file(&manifest_path, CRATE_MANIFEST).unwrap();
+
+        init(&manifest_path, "version_proxy").unwrap();
+
+        assert_eq!(
+            "[workspace]\nmembers = [\"crates/my-app\", \"crates/my-app/version_proxy\"]\n",
+            read(&workspace_manifest_path)
+        );
+        assert_eq!(
+            format!("{CRATE_MANIFEST}version_proxy = {{ path = \"./version_proxy\" }}\n"),
+            read(&manifest_path)
+        );
+        assert!(
+            dir.path()
+                .join("crates/my-app/version_proxy/build.rs")
+                .is_file()
+        );
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, CRATE_MANIFEST).unwrap();
+        init(&manifest_path, "version_proxy").unwrap();
+        let manifest = read(&manifest_path);
+        let build_rs_path = dir.path().join("version_proxy/build.rs");
+        std::fs::write(&build_rs_path, "// customized").unwrap();
+
+        let actions = init(&manifest_path, "version_proxy").unwrap();
+
+        assert_eq!(manifest, read(&manifest_path));
+        assert_eq!("// customized", read(&build_rs_path));
+        assert!(
+            actions.iter().all(|action| action.contains("already")),
+            "{actions:?}"
+        );
+    }
+
+    #[test]
+    fn virtual_manifest() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, "[workspace]\nmembers = []\n").unwrap();
+        let err = init(&manifest_path, "version_proxy").unwrap_err();
+        assert!(err.contains("doesn't have a [package] section"), "{err}");
+        assert!(!dir.path().join("version_proxy").exists());
+    }
+
+    #[test]
+    fn invalid_name() {
+        let dir = TempDir::new("git2version-init-test").unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, CRATE_MANIFEST).unwrap();
+        assert!(init(&manifest_path, "../evil").is_err());
+    }
+}
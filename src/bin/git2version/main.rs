@@ -0,0 +1,52 @@
+//! Command line tool for git2version, see `git2version --help`.
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+
+mod describe;
+mod init;
+mod read;
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the version information embedded into a compiled executable or library.
+    ///
+    /// This only works if the binary was built with `ProxyBuildConfig::embed_link_section` enabled in the proxy crate's `build.rs`.
+    Read(read::ReadArgs),
+
+    /// Print the version information for a git repository, exactly like `init_proxy_build!` would compute it.
+    ///
+    /// This also honors the `GIT2VERSION_*` environment variables and snapshot files. The options correspond to the
+    /// `ProxyBuildConfig` settings. Only the version information of the repository itself is printed, so there are
+    /// no options for `ProxyBuildConfig::submodules` and `ProxyBuildConfig::additional_repository`.
+    Describe(describe::DescribeArgs),
+
+    /// Set up a version proxy crate for a crate.
+    ///
+    /// This creates the proxy crate next to the crate's `Cargo.toml`, adds it as a dependency of the crate
+    /// and as a member of the workspace. Files and entries that already exist are left untouched.
+    Init(init::InitArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Read(args) => read::run(args),
+        Command::Describe(args) => describe::run(args),
+        Command::Init(args) => init::run(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
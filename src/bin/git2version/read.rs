@@ -0,0 +1,212 @@
+use clap::{Args, ValueEnum};
+use git2version::GitInfoOwned;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct ReadArgs {
+    /// Path to the executable or library
+    binary: PathBuf,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// One `key: value` line per field
+    Human,
+
+    /// The version information as JSON, `null` if it wasn't known at build time
+    Json,
+
+    /// `export GIT2VERSION_*=...` lines that can be evaluated by a shell. Building with these environment variables
+    /// reproduces the version information. Nothing is printed if it wasn't known at build time.
+    Shell,
+}
+
+pub fn run(args: ReadArgs) -> Result<(), String> {
+    let git_info = git2version::read_link_section(&args.binary).map_err(|err| err.to_string())?;
+    print!("{}", format_git_info(git_info.as_ref(), args.format));
+    Ok(())
+}
+
+fn format_git_info(git_info: Option<&GitInfoOwned>, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human => format_human(git_info),
+        OutputFormat::Json => {
+            let mut json =
+                serde_json::to_string_pretty(&git_info).expect("Serializing to JSON can't fail");
+            json.push('\n');
+            json
+        }
+        OutputFormat::Shell => format_shell(git_info),
+    }
+}
+
+fn format_human(git_info: Option<&GitInfoOwned>) -> String {
+    let Some(git_info) = git_info else {
+        return "version: unknown\n".to_string();
+    };
+    let mut output = format!("version: {git_info}\n");
+    match &git_info.tag_info {
+        Some(tag_info) => {
+            output += &format!("tag: {}\n", tag_info.tag);
+            output += &format!("commits since tag: {}\n", tag_info.commits_since_tag);
+        }
+        None => output += "tag: none\n",
+    }
+    if !git_info.commit_id.is_empty() {
+        output += &format!("commit: {}\n", git_info.commit_id);
+    }
+    output += &format!("modified: {}\n", git_info.modified);
+    output += &format!("source: {}\n", git_info.source.name());
+    if let Some(worktree) = &git_info.worktree {
+        output += &format!("worktree: {worktree}\n");
+    }
+    output
+}
+
+fn format_shell(git_info: Option<&GitInfoOwned>) -> String {
+    let Some(git_info) = git_info else {
+        return String::new();
+    };
+    // These are the environment variables that `init_proxy_build!` reads as overrides
+    let mut output = String::new();
+    match &git_info.tag_info {
+        Some(tag_info) => {
+            output += &format!("export GIT2VERSION_TAG={}\n", shell_quote(&tag_info.tag));
+            output += &format!(
+                "export GIT2VERSION_COMMITS_SINCE_TAG={}\n",
+                tag_info.commits_since_tag
+            );
+        }
+        None => output += "export GIT2VERSION_TAG=''\n",
+    }
+    // An empty commit id, e.g. for a version from a `Cargo.toml`, is exported as well, since
+    // `init_proxy_build!` needs the variable to be set to substitute for a missing git repository
+    output += &format!(
+        "export GIT2VERSION_COMMIT={}\n",
+        shell_quote(&git_info.commit_id)
+    );
+    output += &format!("export GIT2VERSION_MODIFIED={}\n", git_info.modified);
+    output
+}
+
+/// Quotes `value` so that a POSIX shell reads it as a single word without any expansions.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2version::{TagInfoOwned, VersionSource};
+
+    fn info_with_tag() -> GitInfoOwned {
+        GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::Git,
+            worktree: Some("feature".to_string()),
+        }
+    }
+
+    fn info_without_tag() -> GitInfoOwned {
+        GitInfoOwned {
+            tag_info: None,
+            commit_id: "abcdef1234".to_string(),
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn human_with_tag() {
+        assert_eq!(
+            "version: v1.2.3+5.gabcdef1234.modified\ntag: v1.2.3\ncommits since tag: 5\ncommit: abcdef1234\nmodified: true\nsource: git\nworktree: feature\n",
+            format_git_info(Some(&info_with_tag()), OutputFormat::Human)
+        );
+    }
+
+    #[test]
+    fn human_without_tag() {
+        assert_eq!(
+            "version: unknown.gabcdef1234.environment\ntag: none\ncommit: abcdef1234\nmodified: false\nsource: environment\n",
+            format_git_info(Some(&info_without_tag()), OutputFormat::Human)
+        );
+    }
+
+    #[test]
+    fn human_unknown() {
+        assert_eq!(
+            "version: unknown\n",
+            format_git_info(None, OutputFormat::Human)
+        );
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        for git_info in [Some(info_with_tag()), Some(info_without_tag()), None] {
+            let json = format_git_info(git_info.as_ref(), OutputFormat::Json);
+            assert_eq!(
+                git_info,
+                serde_json::from_str::<Option<GitInfoOwned>>(&json).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn shell_with_tag() {
+        assert_eq!(
+            "export GIT2VERSION_TAG='v1.2.3'\nexport GIT2VERSION_COMMITS_SINCE_TAG=5\nexport GIT2VERSION_COMMIT='abcdef1234'\nexport GIT2VERSION_MODIFIED=true\n",
+            format_git_info(Some(&info_with_tag()), OutputFormat::Shell)
+        );
+    }
+
+    #[test]
+    fn shell_without_tag() {
+        assert_eq!(
+            "export GIT2VERSION_TAG=''\nexport GIT2VERSION_COMMIT='abcdef1234'\nexport GIT2VERSION_MODIFIED=false\n",
+            format_git_info(Some(&info_without_tag()), OutputFormat::Shell)
+        );
+    }
+
+    #[test]
+    fn shell_without_commit_id() {
+        let git_info = GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "1.2.3".to_string(),
+                commits_since_tag: 0,
+            }),
+            commit_id: String::new(),
+            modified: false,
+            source: VersionSource::CargoManifest,
+            worktree: None,
+        };
+        assert_eq!(
+            "export GIT2VERSION_TAG='1.2.3'\nexport GIT2VERSION_COMMITS_SINCE_TAG=0\nexport GIT2VERSION_COMMIT=''\nexport GIT2VERSION_MODIFIED=false\n",
+            format_git_info(Some(&git_info), OutputFormat::Shell)
+        );
+    }
+
+    #[test]
+    fn shell_unknown() {
+        assert_eq!("", format_git_info(None, OutputFormat::Shell));
+    }
+
+    #[test]
+    fn shell_quotes_tag() {
+        let mut git_info = info_with_tag();
+        git_info.tag_info.as_mut().unwrap().tag = "it's $HOME".to_string();
+        assert!(
+            format_git_info(Some(&git_info), OutputFormat::Shell)
+                .starts_with("export GIT2VERSION_TAG='it'\\''s $HOME'\n")
+        );
+    }
+}
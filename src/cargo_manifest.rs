@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+
+use super::gitinfo::VersionSource;
+use super::gitinfo_owned::{GitInfoOwned, TagInfoOwned};
+
+/// The version of a crate as specified in its `Cargo.toml`, i.e. the value cargo exposes as `CARGO_PKG_VERSION`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoManifestVersion {
+    /// The version string, e.g. `"1.2.3"`.
+    pub version: String,
+
+    /// All manifest files that were read to determine the version.
+    /// The build script needs to rerun if any of them changes.
+    pub manifest_paths: Vec<PathBuf>,
+}
+
+impl CargoManifestVersion {
+    /// Converts the cargo package version into version information with [`VersionSource::CargoManifest`] as source.
+    pub fn into_git_info(self) -> GitInfoOwned {
+        GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: self.version,
+                commits_since_tag: 0,
+            }),
+            commit_id: String::new(),
+            modified: false,
+            source: VersionSource::CargoManifest,
+            worktree: None,
+        }
+    }
+}
+
+/// Reads the package version from the `Cargo.toml` in `crate_dir`.
+///
+/// This supports `version.workspace = true` by looking up the `[workspace.package]` version
+/// in the closest ancestor directory containing a `Cargo.toml` with a `[workspace]` section.
+/// Like cargo, a manifest without a `version` field has version `0.0.0`.
+pub fn read_cargo_manifest_version(crate_dir: &Path) -> Result<CargoManifestVersion, String> {
+    let manifest_path = crate_dir.join("Cargo.toml");
+    let manifest = read_manifest(&manifest_path)?;
+    let version = manifest
+        .get("package")
+        .and_then(|package| package.get("version"));
+    match version {
+        None => Ok(CargoManifestVersion {
+            version: "0.0.0".to_string(),
+            manifest_paths: vec![manifest_path],
+        }),
+        Some(toml::Value::String(version)) => Ok(CargoManifestVersion {
+            version: version.clone(),
+            manifest_paths: vec![manifest_path],
+        }),
+        Some(toml::Value::Table(version))
+            if version.get("workspace") == Some(&toml::Value::Boolean(true)) =>
+        {
+            let (workspace_manifest_path, workspace_version) = read_workspace_version(crate_dir)?;
+            Ok(CargoManifestVersion {
+                version: workspace_version,
+                manifest_paths: vec![manifest_path, workspace_manifest_path],
+            })
+        }
+        Some(_) => Err(format!(
+            "Invalid `package.version` field in {}",
+            manifest_path.display()
+        )),
+    }
+}
+
+/// Checks that the tag matches the cargo package version after stripping `tag_prefix` from the tag.
+///
+/// Tags that don't start with `tag_prefix` are compared as they are.
+pub fn check_tag_matches_version(
+    tag: &str,
+    tag_prefix: &str,
+    cargo_version: &str,
+) -> Result<(), String> {
+    let tag_version = tag.strip_prefix(tag_prefix).unwrap_or(tag);
+    if tag_version == cargo_version {
+        Ok(())
+    } else {
+        Err(format!(
+            "HEAD is on tag '{tag}' but the version in Cargo.toml is '{cargo_version}'. Did you forget to bump the version in Cargo.toml?"
+        ))
+    }
+}
+
+fn read_workspace_version(crate_dir: &Path) -> Result<(PathBuf, String), String> {
+    for dir in crate_dir.ancestors().skip(1) {
+        let manifest_path = dir.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest = read_manifest(&manifest_path)?;
+        let Some(workspace) = manifest.get("workspace") else {
+            continue;
+        };
+        let version = workspace
+            .get("package")
+            .and_then(|package| package.get("version"))
+            .and_then(|version| version.as_str());
+        return match version {
+            Some(version) => Ok((manifest_path, version.to_string())),
+            None => Err(format!(
+                "{} doesn't specify `workspace.package.version`",
+                manifest_path.display()
+            )),
+        };
+    }
+    Err(format!(
+        "{} uses `version.workspace = true` but no workspace root was found",
+        crate_dir.join("Cargo.toml").display()
+    ))
+}
+
+fn read_manifest(manifest_path: &Path) -> Result<toml::Table, String> {
+    let content = std::fs::read_to_string(manifest_path)
+        .map_err(|err| format!("Error reading {}: {}", manifest_path.display(), err))?;
+    content
+        .parse::<toml::Table>()
+        .map_err(|err| format!("Error parsing {}: {}", manifest_path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn create_file(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn package_version() {
+        let dir = TempDir::new("test").unwrap();
+        create_file(
+            &dir.path().join("Cargo.toml"),
+            r#"
+[package]
+name = "my-crate"
+version = "1.2.3-alpha"
+            "#,
+        );
+        let version = read_cargo_manifest_version(dir.path()).unwrap();
+        assert_eq!("1.2.3-alpha", version.version);
+        assert_eq!(vec![dir.path().join("Cargo.toml")], version.manifest_paths);
+    }
+
+    #[test]
+    fn missing_version_defaults_to_zero() {
+        let dir = TempDir::new("test").unwrap();
+        create_file(
+            &dir.path().join("Cargo.toml"),
+            r#"
+[package]
+name = "my-crate"
+            "#,
+        );
+        let version = read_cargo_manifest_version(dir.path()).unwrap();
+        assert_eq!("0.0.0", version.version);
+    }
+
+    #[test]
+    fn workspace_version() {
+        let dir = TempDir::new("test").unwrap();
+        create_file(
+            &dir.path().join("Cargo.toml"),
+            r#"
+[workspace]
+members = ["crates/my-crate"]
+
+[workspace.package]
+version = "2.0.0"
+            "#,
+        );
+        create_file(
+            &dir.path().join("crates/my-crate/Cargo.toml"),
+            r#"
+[package]
+name = "my-crate"
+version.workspace = true
+            "#,
+        );
+        let crate_dir = dir.path().join("crates/my-crate");
+        let version = read_cargo_manifest_version(&crate_dir).unwrap();
+        assert_eq!("2.0.0", version.version);
+        assert_eq!(
+            vec![crate_dir.join("Cargo.toml"), dir.path().join("Cargo.toml")],
+            version.manifest_paths
+        );
+    }
+
+    #[test]
+    fn workspace_without_package_version() {
+        let dir = TempDir::new("test").unwrap();
+        create_file(
+            &dir.path().join("Cargo.toml"),
+            r#"
+[workspace]
+members = ["my-crate"]
+            "#,
+        );
+        create_file(
+            &dir.path().join("my-crate/Cargo.toml"),
+            r#"
+[package]
+name = "my-crate"
+version = { workspace = true }
+            "#,
+        );
+        assert!(read_cargo_manifest_version(&dir.path().join("my-crate")).is_err());
+    }
+
+    #[test]
+    fn missing_manifest() {
+        let dir = TempDir::new("test").unwrap();
+        assert!(read_cargo_manifest_version(dir.path()).is_err());
+    }
+
+    #[test]
+    fn tag_matches_version() {
+        assert!(check_tag_matches_version("v1.2.3", "v", "1.2.3").is_ok());
+        assert!(check_tag_matches_version("1.2.3", "v", "1.2.3").is_ok());
+        assert!(check_tag_matches_version("release-1.2.3", "release-", "1.2.3").is_ok());
+        assert!(check_tag_matches_version("v1.2.3-alpha", "v", "1.2.3-alpha").is_ok());
+        assert!(check_tag_matches_version("v1.2.3", "", "v1.2.3").is_ok());
+    }
+
+    #[test]
+    fn tag_doesnt_match_version() {
+        assert!(check_tag_matches_version("v1.2.3", "v", "1.2.4").is_err());
+        assert!(check_tag_matches_version("v1.2.3", "", "1.2.3").is_err());
+        assert!(check_tag_matches_version("v1.2.3-alpha", "v", "1.2.3").is_err());
+        assert!(check_tag_matches_version("release-1.2.3", "v", "1.2.3").is_err());
+    }
+
+    #[test]
+    fn into_git_info() {
+        let version = CargoManifestVersion {
+            version: "1.2.3".to_string(),
+            manifest_paths: vec![],
+        };
+        assert_eq!(
+            GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "1.2.3".to_string(),
+                    commits_since_tag: 0,
+                }),
+                commit_id: String::new(),
+                modified: false,
+                source: VersionSource::CargoManifest,
+                worktree: None,
+            },
+            version.into_git_info()
+        );
+    }
+}
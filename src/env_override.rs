@@ -0,0 +1,374 @@
+use super::COMMIT_ID_SHORT_HASH_LENGTH;
+use super::gitinfo::VersionSource;
+use super::gitinfo_owned::{GitInfoOwned, TagInfoOwned};
+
+/// Environment variable overriding the tag name. Setting it to an empty string means "no tag".
+pub const ENV_TAG: &str = "GIT2VERSION_TAG";
+/// Environment variable overriding the number of commits since the tag.
+pub const ENV_COMMITS_SINCE_TAG: &str = "GIT2VERSION_COMMITS_SINCE_TAG";
+/// Environment variable overriding the commit id. Setting it to an empty string means "unknown commit id".
+pub const ENV_COMMIT: &str = "GIT2VERSION_COMMIT";
+/// Environment variable overriding the modified flag.
+pub const ENV_MODIFIED: &str = "GIT2VERSION_MODIFIED";
+
+/// All environment variables that are read by [`EnvOverrides::from_env`] for the unnamed proxy.
+pub const ALL_ENV_VARS: [&str; 4] = [ENV_TAG, ENV_COMMITS_SINCE_TAG, ENV_COMMIT, ENV_MODIFIED];
+
+/// Returns the name of the environment variable `var` (one of [`ALL_ENV_VARS`]) for the proxy `proxy_name`.
+///
+/// The unnamed proxy uses the names as they are, a proxy named `"server"` uses e.g. `GIT2VERSION_SERVER__TAG` instead of `GIT2VERSION_TAG`.
+/// The double underscore can't appear in the suffixes, so different proxies never share a variable, even if a proxy name ends with
+/// a suffix like `commits_since`.
+pub fn env_var_name(var: &str, proxy_name: Option<&str>) -> String {
+    match proxy_name {
+        None => var.to_string(),
+        Some(proxy_name) => {
+            let suffix = var
+                .strip_prefix("GIT2VERSION_")
+                .expect("All environment variables start with GIT2VERSION_");
+            let proxy_name = proxy_name.to_ascii_uppercase();
+            format!("GIT2VERSION_{proxy_name}__{suffix}")
+        }
+    }
+}
+
+/// Version information overrides read from `GIT2VERSION_*` environment variables.
+///
+/// This allows distribution packagers to inject the real version when building from
+/// a source tarball that doesn't contain the `.git` directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EnvOverrides {
+    /// `None` if not set, `Some(None)` if set to an empty string (i.e. "no tag").
+    tag: Option<Option<String>>,
+    commits_since_tag: Option<u32>,
+    commit_id: Option<String>,
+    modified: Option<bool>,
+    /// Name of the commit id variable, for error messages.
+    commit_env_var: String,
+}
+
+impl EnvOverrides {
+    /// Reads the overrides for the proxy `proxy_name` from the environment of the current process, see [`env_var_name`].
+    pub fn from_env(proxy_name: Option<&str>) -> Result<Self, String> {
+        Self::from_lookup(proxy_name, |name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(
+        proxy_name: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let env_tag = env_var_name(ENV_TAG, proxy_name);
+        let env_commits_since_tag = env_var_name(ENV_COMMITS_SINCE_TAG, proxy_name);
+        let env_commit = env_var_name(ENV_COMMIT, proxy_name);
+        let env_modified = env_var_name(ENV_MODIFIED, proxy_name);
+
+        let tag = lookup(&env_tag).map(|tag| if tag.is_empty() { None } else { Some(tag) });
+        let commits_since_tag = lookup(&env_commits_since_tag)
+            .map(|value| {
+                value.trim().parse::<u32>().map_err(|err| {
+                    format!("Invalid value '{value}' for {env_commits_since_tag}: {err}")
+                })
+            })
+            .transpose()?;
+        let commit_id = lookup(&env_commit)
+            .map(|value| {
+                let value = value.trim();
+                if !value.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!(
+                        "Invalid value '{value}' for {env_commit}: expected a hexadecimal commit id"
+                    ));
+                }
+                // Allow passing in full commit hashes, but shorten them the same way we shorten commit ids from git
+                let shortened = &value[..value.len().min(COMMIT_ID_SHORT_HASH_LENGTH)];
+                Ok(shortened.to_ascii_lowercase())
+            })
+            .transpose()?;
+        let modified = lookup(&env_modified)
+            .map(|value| match value.trim() {
+                "true" | "1" => Ok(true),
+                "false" | "0" => Ok(false),
+                _ => Err(format!(
+                    "Invalid value '{value}' for {env_modified}: expected 'true' or 'false'"
+                )),
+            })
+            .transpose()?;
+
+        if commits_since_tag.is_some() && !matches!(tag, Some(Some(_))) {
+            return Err(format!(
+                "{env_commits_since_tag} is set but {env_tag} isn't. Please also set {env_tag}."
+            ));
+        }
+
+        Ok(Self {
+            tag,
+            commits_since_tag,
+            commit_id,
+            modified,
+            commit_env_var: env_commit,
+        })
+    }
+
+    /// Returns true if none of the environment variables were set.
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none()
+            && self.commits_since_tag.is_none()
+            && self.commit_id.is_none()
+            && self.modified.is_none()
+    }
+
+    /// Applies the overrides to the version information read from git.
+    ///
+    /// If `git_info` is `Some`, each set environment variable overrides the corresponding field.
+    /// If `git_info` is `None`, e.g. because there is no git repository, the overrides substitute
+    /// for it, but this requires at least `GIT2VERSION_COMMIT` to be set.
+    /// In both cases, the resulting source is [`VersionSource::Environment`].
+    pub fn apply(self, git_info: Option<GitInfoOwned>) -> Result<Option<GitInfoOwned>, String> {
+        if self.is_empty() {
+            return Ok(git_info);
+        }
+        let mut git_info = match (git_info, self.commit_id) {
+            (Some(mut git_info), commit_id) => {
+                if let Some(commit_id) = commit_id {
+                    git_info.commit_id = commit_id;
+                }
+                git_info
+            }
+            (None, Some(commit_id)) => GitInfoOwned {
+                tag_info: None,
+                commit_id,
+                modified: false,
+                source: VersionSource::Environment,
+                worktree: None,
+            },
+            (None, None) => {
+                return Err(format!(
+                    "Version overrides were set in the environment, but there is no git repository to take the commit id from. Please also set {}.",
+                    self.commit_env_var
+                ));
+            }
+        };
+        match self.tag {
+            None => {}
+            Some(None) => git_info.tag_info = None,
+            Some(Some(tag)) => {
+                git_info.tag_info = Some(TagInfoOwned {
+                    tag,
+                    commits_since_tag: self.commits_since_tag.unwrap_or(0),
+                })
+            }
+        }
+        if let Some(modified) = self.modified {
+            git_info.modified = modified;
+        }
+        git_info.source = VersionSource::Environment;
+        Ok(Some(git_info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn overrides(vars: &[(&str, &str)]) -> Result<EnvOverrides, String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        EnvOverrides::from_lookup(None, |name| vars.get(name).cloned())
+    }
+
+    fn some_git_info() -> GitInfoOwned {
+        GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.0.0".to_string(),
+                commits_since_tag: 3,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::Git,
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn no_vars_set() {
+        let overrides = overrides(&[]).unwrap();
+        assert!(overrides.is_empty());
+        assert_eq!(None, overrides.clone().apply(None).unwrap());
+        assert_eq!(
+            Some(some_git_info()),
+            overrides.apply(Some(some_git_info())).unwrap()
+        );
+    }
+
+    #[test]
+    fn substitute_without_repository() {
+        let overrides = overrides(&[
+            (ENV_TAG, "v2.0.0"),
+            (ENV_COMMITS_SINCE_TAG, "7"),
+            (ENV_COMMIT, "0123456789"),
+            (ENV_MODIFIED, "false"),
+        ])
+        .unwrap();
+        assert_eq!(
+            Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v2.0.0".to_string(),
+                    commits_since_tag: 7,
+                }),
+                commit_id: "0123456789".to_string(),
+                modified: false,
+                source: VersionSource::Environment,
+                worktree: None,
+            }),
+            overrides.apply(None).unwrap()
+        );
+    }
+
+    #[test]
+    fn substitute_without_repository_requires_commit() {
+        let overrides = overrides(&[(ENV_TAG, "v2.0.0")]).unwrap();
+        assert!(overrides.apply(None).is_err());
+    }
+
+    #[test]
+    fn substitute_with_only_commit() {
+        let overrides = overrides(&[(ENV_COMMIT, "0123456789")]).unwrap();
+        assert_eq!(
+            Some(GitInfoOwned {
+                tag_info: None,
+                commit_id: "0123456789".to_string(),
+                modified: false,
+                source: VersionSource::Environment,
+                worktree: None,
+            }),
+            overrides.apply(None).unwrap()
+        );
+    }
+
+    #[test]
+    fn override_tag_only() {
+        let overrides = overrides(&[(ENV_TAG, "v2.0.0")]).unwrap();
+        assert_eq!(
+            Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v2.0.0".to_string(),
+                    commits_since_tag: 0,
+                }),
+                commit_id: "abcdef1234".to_string(),
+                modified: true,
+                source: VersionSource::Environment,
+                worktree: None,
+            }),
+            overrides.apply(Some(some_git_info())).unwrap()
+        );
+    }
+
+    #[test]
+    fn override_empty_tag_removes_tag() {
+        let overrides = overrides(&[(ENV_TAG, "")]).unwrap();
+        let result = overrides.apply(Some(some_git_info())).unwrap().unwrap();
+        assert_eq!(None, result.tag_info);
+    }
+
+    #[test]
+    fn override_modified_and_commit() {
+        let overrides = overrides(&[(ENV_MODIFIED, "0"), (ENV_COMMIT, "fedcba9876")]).unwrap();
+        let result = overrides.apply(Some(some_git_info())).unwrap().unwrap();
+        assert_eq!("fedcba9876", result.commit_id);
+        assert!(!result.modified);
+        assert_eq!(some_git_info().tag_info, result.tag_info);
+        assert_eq!(VersionSource::Environment, result.source);
+    }
+
+    #[test]
+    fn full_commit_hash_gets_shortened() {
+        let overrides =
+            overrides(&[(ENV_COMMIT, "A9EBD080A7B1C76A8B3F3080A7B1C7C76A8B3F30")]).unwrap();
+        let result = overrides.apply(None).unwrap().unwrap();
+        assert_eq!("a9ebd080a7", result.commit_id);
+    }
+
+    #[test]
+    fn empty_commit_means_unknown_commit_id() {
+        let overrides = overrides(&[(ENV_TAG, "1.2.3"), (ENV_COMMIT, "")]).unwrap();
+        assert_eq!(
+            Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "1.2.3".to_string(),
+                    commits_since_tag: 0,
+                }),
+                commit_id: String::new(),
+                modified: false,
+                source: VersionSource::Environment,
+                worktree: None,
+            }),
+            overrides.apply(None).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(overrides(&[(ENV_TAG, "v1"), (ENV_COMMITS_SINCE_TAG, "abc")]).is_err());
+        assert!(overrides(&[(ENV_TAG, "v1"), (ENV_COMMITS_SINCE_TAG, "-1")]).is_err());
+        assert!(overrides(&[(ENV_COMMIT, "not-a-hash")]).is_err());
+        assert!(overrides(&[(ENV_MODIFIED, "yes")]).is_err());
+    }
+
+    #[test]
+    fn commits_since_tag_without_tag() {
+        assert!(overrides(&[(ENV_COMMITS_SINCE_TAG, "5")]).is_err());
+        assert!(overrides(&[(ENV_TAG, ""), (ENV_COMMITS_SINCE_TAG, "5")]).is_err());
+    }
+
+    #[test]
+    fn named_proxy() {
+        assert_eq!(
+            "GIT2VERSION_MY_SERVER__TAG",
+            env_var_name(ENV_TAG, Some("my_server"))
+        );
+        assert_eq!(ENV_TAG, env_var_name(ENV_TAG, None));
+
+        let vars: HashMap<&str, &str> =
+            [(ENV_TAG, "v1.0.0"), ("GIT2VERSION_SERVER__TAG", "v2.0.0")]
+                .into_iter()
+                .collect();
+        let overrides = EnvOverrides::from_lookup(Some("server"), |name| {
+            vars.get(name).map(|value| value.to_string())
+        })
+        .unwrap();
+        let err = overrides.apply(None).unwrap_err();
+        assert!(err.contains("GIT2VERSION_SERVER__COMMIT"), "{err}");
+    }
+
+    #[test]
+    fn named_proxy_doesnt_collide_with_unnamed_proxy() {
+        // Without a separator, this would be GIT2VERSION_COMMITS_SINCE_TAG
+        assert_ne!(
+            ENV_COMMITS_SINCE_TAG,
+            env_var_name(ENV_TAG, Some("commits_since"))
+        );
+        for var in ALL_ENV_VARS {
+            for other_var in ALL_ENV_VARS {
+                assert_ne!(var, env_var_name(other_var, Some("commits_since")));
+            }
+        }
+    }
+
+    #[test]
+    fn named_proxies_dont_collide() {
+        // Without a separator, both would be GIT2VERSION_A_COMMITS_SINCE_TAG
+        assert_ne!(
+            env_var_name(ENV_COMMITS_SINCE_TAG, Some("a")),
+            env_var_name(ENV_TAG, Some("a_commits_since"))
+        );
+        let names = ["a", "a_", "a_commits_since", "commits_since", "a__commits"];
+        let mut all = std::collections::HashSet::new();
+        for name in names {
+            for var in ALL_ENV_VARS {
+                assert!(all.insert(env_var_name(var, Some(name))), "{name} {var}");
+            }
+        }
+    }
+}
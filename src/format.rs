@@ -0,0 +1,551 @@
+use core::fmt::{self, Display, Formatter, Write};
+
+use super::gitinfo::GitInfo;
+
+/// Output formats that a [`GitInfo`] can be rendered in, see [`GitInfo::display_as`].
+///
+/// Apart from [`VersionFormat::GitDescribe`], all formats are meant for version numbers and therefore strip a leading `v`
+/// from the tag (e.g. `v1.2.3` becomes `1.2.3`) and use `0.0.0` (or `0` for PEP 440) as version if there is no tag.
+/// The formats don't include the [`source`](GitInfo::source) of the version information.
+///
+/// In the examples below, the tag is `v1.2.3`, there are 5 commits since the tag, the commit id is `abcdef1234`
+/// and the working directory is modified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VersionFormat {
+    /// The format of [`GitInfo`]'s [`Display`] implementation, e.g. `v1.2.3+5.gabcdef1234.modified`.
+    Default,
+
+    /// The format of `git describe --tags --long --dirty`, e.g. `v1.2.3-5-gabcdef1234-dirty`.
+    ///
+    /// If there is no tag, this is just the commit id, like `git describe --always` does, e.g. `abcdef1234-dirty`.
+    GitDescribe,
+
+    /// A [SemVer](https://semver.org) version with build metadata, e.g. `1.2.3+5.gabcdef1234.dirty`.
+    ///
+    /// If the tag already contains build metadata, our build metadata is appended to it, e.g. `1.2.3+meta.5.gabcdef1234`.
+    SemVer,
+
+    /// A [PEP 440](https://peps.python.org/pep-0440/) version with a post release segment and a local version label,
+    /// e.g. `1.2.3.post5+gabcdef1234.dirty`.
+    ///
+    /// The `.post{n}` segment is omitted if HEAD is exactly on the tag. The tag itself must be a valid PEP 440 version
+    /// (after stripping the leading `v`), it isn't normalized.
+    Pep440,
+
+    /// A [Debian](https://www.debian.org/doc/debian-policy/ch-controlfields.html#version) upstream version,
+    /// e.g. `1.2.3+git5.abcdef1234.dirty`.
+    ///
+    /// Dashes in the tag are replaced with `~` so that pre-releases like `1.2.3-alpha` sort before `1.2.3`,
+    /// and other characters that aren't allowed in Debian versions are replaced with `.`.
+    Debian,
+
+    /// A version that is safe to use as the `Version` field of an RPM package, e.g. `1.2.3+5.gabcdef1234.dirty`.
+    ///
+    /// Like [`VersionFormat::SemVer`], but dashes in the tag are replaced with `~` so that pre-releases like `1.2.3-alpha`
+    /// sort before `1.2.3`, and other characters that aren't allowed in RPM versions are replaced with `_`.
+    Rpm,
+}
+
+impl VersionFormat {
+    /// All version formats.
+    pub const ALL: [Self; 6] = [
+        Self::Default,
+        Self::GitDescribe,
+        Self::SemVer,
+        Self::Pep440,
+        Self::Debian,
+        Self::Rpm,
+    ];
+
+    /// Returns a short name for the format, e.g. `"git-describe"` or `"pep440"`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::GitDescribe => "git-describe",
+            Self::SemVer => "semver",
+            Self::Pep440 => "pep440",
+            Self::Debian => "debian",
+            Self::Rpm => "rpm",
+        }
+    }
+
+    /// Inverse of [`VersionFormat::name`]. Returns `None` if `name` isn't a known format.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+impl<'a, 'b> GitInfo<'a, 'b> {
+    /// Returns an object that renders this version information in the given format when displayed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git2version::{GitInfo, TagInfo, VersionFormat, VersionSource};
+    ///
+    /// let info = GitInfo {
+    ///     tag_info: Some(TagInfo {
+    ///         tag: "v1.2.3",
+    ///         commits_since_tag: 5,
+    ///     }),
+    ///     commit_id: "abcdef1234",
+    ///     modified: true,
+    ///     source: VersionSource::Git,
+    ///     worktree: None,
+    /// };
+    /// assert_eq!(info.display_as(VersionFormat::GitDescribe).to_string(), "v1.2.3-5-gabcdef1234-dirty");
+    /// assert_eq!(info.display_as(VersionFormat::SemVer).to_string(), "1.2.3+5.gabcdef1234.dirty");
+    /// assert_eq!(info.display_as(VersionFormat::Pep440).to_string(), "1.2.3.post5+gabcdef1234.dirty");
+    /// assert_eq!(info.display_as(VersionFormat::Debian).to_string(), "1.2.3+git5.abcdef1234.dirty");
+    /// assert_eq!(info.display_as(VersionFormat::Rpm).to_string(), "1.2.3+5.gabcdef1234.dirty");
+    /// ```
+    pub fn display_as(&self, format: VersionFormat) -> DisplayAs<'_, 'a, 'b> {
+        DisplayAs { info: self, format }
+    }
+}
+
+/// Renders a [`GitInfo`] in a given [`VersionFormat`]. Created by [`GitInfo::display_as`].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayAs<'i, 'a, 'b> {
+    info: &'i GitInfo<'a, 'b>,
+    format: VersionFormat,
+}
+
+impl Display for DisplayAs<'_, '_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let info = self.info;
+        let commits_since_tag = info.tag_info.map(|tag_info| tag_info.commits_since_tag);
+        match self.format {
+            VersionFormat::Default => Display::fmt(info, f),
+            VersionFormat::GitDescribe => {
+                if let Some(tag_info) = info.tag_info {
+                    write!(f, "{}-{}", tag_info.tag, tag_info.commits_since_tag)?;
+                    if !info.commit_id.is_empty() {
+                        write!(f, "-g{}", info.commit_id)?;
+                    }
+                } else {
+                    write!(f, "{}", info.commit_id)?;
+                }
+                if info.modified {
+                    write!(f, "-dirty")?;
+                }
+                Ok(())
+            }
+            VersionFormat::SemVer | VersionFormat::Rpm => {
+                let version = version_from_tag(info).unwrap_or("0.0.0");
+                if self.format == VersionFormat::Rpm {
+                    write_replaced(f, version, |c| match c {
+                        '-' => '~',
+                        c if c.is_ascii_alphanumeric() || "._+~^".contains(c) => c,
+                        _ => '_',
+                    })?;
+                } else {
+                    write!(f, "{version}")?;
+                }
+                let mut separator = if version.contains('+') { '.' } else { '+' };
+                let mut write_metadata = |f: &mut Formatter<'_>, metadata: &dyn Display| {
+                    let result = write!(f, "{separator}{metadata}");
+                    separator = '.';
+                    result
+                };
+                if let Some(commits_since_tag) = commits_since_tag {
+                    write_metadata(f, &commits_since_tag)?;
+                }
+                if !info.commit_id.is_empty() {
+                    write_metadata(f, &format_args!("g{}", info.commit_id))?;
+                }
+                if info.modified {
+                    write_metadata(f, &"dirty")?;
+                }
+                Ok(())
+            }
+            VersionFormat::Pep440 => {
+                write!(f, "{}", version_from_tag(info).unwrap_or("0"))?;
+                if let Some(commits_since_tag) = commits_since_tag
+                    && commits_since_tag > 0
+                {
+                    write!(f, ".post{commits_since_tag}")?;
+                }
+                let mut separator = '+';
+                if !info.commit_id.is_empty() {
+                    write!(f, "{separator}g{}", info.commit_id)?;
+                    separator = '.';
+                }
+                if info.modified {
+                    write!(f, "{separator}dirty")?;
+                }
+                Ok(())
+            }
+            VersionFormat::Debian => {
+                write_replaced(f, version_from_tag(info).unwrap_or("0"), |c| match c {
+                    '-' => '~',
+                    c if c.is_ascii_alphanumeric() || ".+~".contains(c) => c,
+                    _ => '.',
+                })?;
+                write!(f, "+git")?;
+                if let Some(commits_since_tag) = commits_since_tag {
+                    write!(f, "{commits_since_tag}")?;
+                }
+                if !info.commit_id.is_empty() {
+                    write!(f, ".{}", info.commit_id)?;
+                }
+                if info.modified {
+                    write!(f, ".dirty")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the tag with a leading `v` or `V` stripped if it is followed by a digit, or `None` if there is no tag.
+pub(crate) fn version_from_tag<'a>(info: &GitInfo<'a, '_>) -> Option<&'a str> {
+    let tag = info.tag_info?.tag;
+    let stripped = tag
+        .strip_prefix('v')
+        .or_else(|| tag.strip_prefix('V'))
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+    Some(stripped.unwrap_or(tag))
+}
+
+fn write_replaced(
+    f: &mut Formatter<'_>,
+    value: &str,
+    replace: impl Fn(char) -> char,
+) -> fmt::Result {
+    value.chars().try_for_each(|c| f.write_char(replace(c)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TagInfo, VersionSource};
+
+    fn info(
+        tag: Option<(&'static str, u32)>,
+        commit_id: &'static str,
+        modified: bool,
+    ) -> GitInfo<'static, 'static> {
+        GitInfo {
+            tag_info: tag.map(|(tag, commits_since_tag)| TagInfo {
+                tag,
+                commits_since_tag,
+            }),
+            commit_id,
+            modified,
+            source: VersionSource::Git,
+            worktree: None,
+        }
+    }
+
+    fn all_infos() -> Vec<GitInfo<'static, 'static>> {
+        let mut result = vec![];
+        for tag in [
+            None,
+            Some(("v1.2.3", 0)),
+            Some(("v1.2.3", 5)),
+            Some(("1.2.3", 5)),
+            Some(("v1.2.3-alpha.1", 5)),
+        ] {
+            for modified in [false, true] {
+                result.push(info(tag, "abcdef1234", modified));
+            }
+        }
+        result
+    }
+
+    /// Parsed representation of a formatted version: (version or tag, commits since tag, commit id, modified)
+    type Parsed = (Option<String>, Option<u32>, String, bool);
+
+    fn expected(info: &GitInfo, version: impl Fn(&str) -> String) -> Parsed {
+        (
+            info.tag_info.map(|tag_info| version(tag_info.tag)),
+            info.tag_info.map(|tag_info| tag_info.commits_since_tag),
+            info.commit_id.to_string(),
+            info.modified,
+        )
+    }
+
+    fn strip_v(tag: &str) -> String {
+        tag.strip_prefix('v').unwrap_or(tag).to_string()
+    }
+
+    fn parse_git_describe(s: &str) -> Parsed {
+        let (s, modified) = match s.strip_suffix("-dirty") {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let Some((rest, commit_id)) = s.rsplit_once("-g") else {
+            return (None, None, s.to_string(), modified);
+        };
+        let (tag, commits_since_tag) = rest.rsplit_once('-').unwrap();
+        (
+            Some(tag.to_string()),
+            Some(commits_since_tag.parse().unwrap()),
+            commit_id.to_string(),
+            modified,
+        )
+    }
+
+    fn parse_semver(s: &str) -> Parsed {
+        let version = semver::Version::parse(s).unwrap();
+        let metadata: Vec<&str> = version.build.as_str().split('.').collect();
+        let (metadata, modified) = match metadata.split_last() {
+            Some((&"dirty", rest)) => (rest, true),
+            _ => (&metadata[..], false),
+        };
+        let (commits_since_tag, commit_id) = match metadata {
+            [commits_since_tag, commit_id] => (
+                Some(commits_since_tag.parse().unwrap()),
+                commit_id.strip_prefix('g').unwrap(),
+            ),
+            [commit_id] => (None, commit_id.strip_prefix('g').unwrap()),
+            _ => panic!("Unexpected build metadata in {s}"),
+        };
+        let version = semver::Version {
+            build: semver::BuildMetadata::EMPTY,
+            ..version
+        };
+        let version = commits_since_tag.map(|_| version.to_string());
+        (version, commits_since_tag, commit_id.to_string(), modified)
+    }
+
+    fn parse_pep440(s: &str) -> Parsed {
+        let (public, local) = s.split_once('+').unwrap();
+        let (commit_id, modified) = match local.split_once('.') {
+            Some((commit_id, "dirty")) => (commit_id, true),
+            None => (local, false),
+            _ => panic!("Unexpected local version label in {s}"),
+        };
+        let commit_id = commit_id.strip_prefix('g').unwrap().to_string();
+        if public == "0" {
+            return (None, None, commit_id, modified);
+        }
+        let (version, commits_since_tag) = match public.rsplit_once(".post") {
+            Some((version, post)) => (version, post.parse().unwrap()),
+            None => (public, 0),
+        };
+        (
+            Some(version.to_string()),
+            Some(commits_since_tag),
+            commit_id,
+            modified,
+        )
+    }
+
+    fn parse_debian(s: &str) -> Parsed {
+        let (version, git) = s.rsplit_once("+git").unwrap();
+        let (git, modified) = match git.strip_suffix(".dirty") {
+            Some(git) => (git, true),
+            None => (git, false),
+        };
+        let (commits_since_tag, commit_id) = git.split_once('.').unwrap();
+        if commits_since_tag.is_empty() {
+            assert_eq!("0", version);
+            return (None, None, commit_id.to_string(), modified);
+        }
+        (
+            Some(version.to_string()),
+            Some(commits_since_tag.parse().unwrap()),
+            commit_id.to_string(),
+            modified,
+        )
+    }
+
+    fn parse_rpm(s: &str) -> Parsed {
+        assert!(
+            s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._+~^".contains(c)),
+            "{s} isn't a valid RPM version"
+        );
+        // Apart from the tag, the RPM format is the same as the SemVer format
+        let (version, rest) = s.split_once('+').unwrap();
+        let (version, commits_since_tag, commit_id, modified) =
+            parse_semver(&format!("{}+{rest}", version.replace('~', "-")));
+        (
+            version.map(|version| version.replace('-', "~")),
+            commits_since_tag,
+            commit_id,
+            modified,
+        )
+    }
+
+    #[test]
+    fn git_describe_roundtrip() {
+        for info in all_infos() {
+            let formatted = info.display_as(VersionFormat::GitDescribe).to_string();
+            assert_eq!(
+                expected(&info, str::to_string),
+                parse_git_describe(&formatted),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn semver_roundtrip() {
+        for info in all_infos() {
+            let formatted = info.display_as(VersionFormat::SemVer).to_string();
+            assert_eq!(
+                expected(&info, strip_v),
+                parse_semver(&formatted),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn pep440_roundtrip() {
+        for info in all_infos() {
+            let formatted = info.display_as(VersionFormat::Pep440).to_string();
+            assert_eq!(
+                expected(&info, strip_v),
+                parse_pep440(&formatted),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn debian_roundtrip() {
+        for info in all_infos() {
+            let formatted = info.display_as(VersionFormat::Debian).to_string();
+            assert_eq!(
+                expected(&info, |tag| strip_v(tag).replace('-', "~")),
+                parse_debian(&formatted),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn rpm_roundtrip() {
+        for info in all_infos() {
+            let formatted = info.display_as(VersionFormat::Rpm).to_string();
+            assert_eq!(
+                expected(&info, |tag| strip_v(tag).replace('-', "~")),
+                parse_rpm(&formatted),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn default_is_display() {
+        for info in all_infos() {
+            assert_eq!(
+                info.to_string(),
+                info.display_as(VersionFormat::Default).to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn examples() {
+        let on_tag = info(Some(("v1.2.3-alpha", 0)), "abcdef1234", false);
+        assert_eq!(
+            "v1.2.3-alpha-0-gabcdef1234",
+            on_tag.display_as(VersionFormat::GitDescribe).to_string()
+        );
+        assert_eq!(
+            "1.2.3-alpha+0.gabcdef1234",
+            on_tag.display_as(VersionFormat::SemVer).to_string()
+        );
+        assert_eq!(
+            "1.2.3-alpha+gabcdef1234",
+            on_tag.display_as(VersionFormat::Pep440).to_string()
+        );
+        assert_eq!(
+            "1.2.3~alpha+git0.abcdef1234",
+            on_tag.display_as(VersionFormat::Debian).to_string()
+        );
+        assert_eq!(
+            "1.2.3~alpha+0.gabcdef1234",
+            on_tag.display_as(VersionFormat::Rpm).to_string()
+        );
+
+        let no_tag = info(None, "abcdef1234", true);
+        assert_eq!(
+            "abcdef1234-dirty",
+            no_tag.display_as(VersionFormat::GitDescribe).to_string()
+        );
+        assert_eq!(
+            "0.0.0+gabcdef1234.dirty",
+            no_tag.display_as(VersionFormat::SemVer).to_string()
+        );
+        assert_eq!(
+            "0+gabcdef1234.dirty",
+            no_tag.display_as(VersionFormat::Pep440).to_string()
+        );
+        assert_eq!(
+            "0+git.abcdef1234.dirty",
+            no_tag.display_as(VersionFormat::Debian).to_string()
+        );
+        assert_eq!(
+            "0.0.0+gabcdef1234.dirty",
+            no_tag.display_as(VersionFormat::Rpm).to_string()
+        );
+    }
+
+    #[test]
+    fn tag_with_build_metadata() {
+        let info = info(Some(("v1.2.3+meta", 5)), "abcdef1234", false);
+        assert_eq!(
+            "1.2.3+meta.5.gabcdef1234",
+            info.display_as(VersionFormat::SemVer).to_string()
+        );
+    }
+
+    #[test]
+    fn tag_with_invalid_characters() {
+        let info = info(Some(("v1.2.3_rc/1", 5)), "abcdef1234", false);
+        assert_eq!(
+            "1.2.3.rc.1+git5.abcdef1234",
+            info.display_as(VersionFormat::Debian).to_string()
+        );
+        assert_eq!(
+            "1.2.3_rc_1+5.gabcdef1234",
+            info.display_as(VersionFormat::Rpm).to_string()
+        );
+    }
+
+    #[test]
+    fn tag_starting_with_v_but_not_version() {
+        let info = info(Some(("very-early", 5)), "abcdef1234", false);
+        assert_eq!(
+            "very-early+5.gabcdef1234",
+            info.display_as(VersionFormat::SemVer).to_string()
+        );
+    }
+
+    #[test]
+    fn empty_commit_id() {
+        let info = GitInfo {
+            source: VersionSource::CargoManifest,
+            worktree: None,
+            ..info(Some(("1.2.3", 0)), "", false)
+        };
+        assert_eq!(
+            "1.2.3-0",
+            info.display_as(VersionFormat::GitDescribe).to_string()
+        );
+        assert_eq!(
+            "1.2.3+0",
+            info.display_as(VersionFormat::SemVer).to_string()
+        );
+        assert_eq!("1.2.3", info.display_as(VersionFormat::Pep440).to_string());
+        assert_eq!(
+            "1.2.3+git0",
+            info.display_as(VersionFormat::Debian).to_string()
+        );
+        assert_eq!("1.2.3+0", info.display_as(VersionFormat::Rpm).to_string());
+    }
+
+    #[test]
+    fn name_roundtrip() {
+        for format in VersionFormat::ALL {
+            assert_eq!(Some(format), VersionFormat::from_name(format.name()));
+        }
+        assert_eq!(None, VersionFormat::from_name("unknown"));
+    }
+}
@@ -0,0 +1,509 @@
+use git2::{Repository, StatusOptions, StatusShow};
+
+use super::COMMIT_ID_SHORT_HASH_LENGTH;
+use super::git_helpers;
+use super::gitinfo::VersionSource;
+use super::gitinfo_owned::{GitInfoOwned, SubmoduleInfoOwned, TagInfoOwned};
+
+/// Retrieves git version information from the given repository.
+///
+/// This function is called by the [`init_proxy_build!`](crate::init_proxy_build) macro
+/// in the proxy crate's `build.rs` to extract version information from git.
+///
+/// # Arguments
+///
+/// * `repo` - A reference to an opened git2 [`Repository`]
+///
+/// # Returns
+///
+/// Returns a [`GitInfoOwned`] containing:
+/// - The closest ancestor tag (if any) and commits since that tag
+/// - The shortened HEAD commit ID (10 characters)
+/// - Whether the working directory has modifications (always `false` for bare repositories)
+/// - The name of the linked worktree, if `repo` was opened from one
+///
+/// # Errors
+///
+/// Returns a [`git2::Error`] if:
+/// - The repository HEAD cannot be resolved (e.g., empty repository with no commits)
+/// - Git status cannot be retrieved
+/// - Tag names contain non-UTF8 characters
+///
+/// # Tag Resolution
+///
+/// Tags are resolved by walking the first-parent history (ignoring merge commits)
+/// from HEAD until a tagged commit is found. If multiple tags exist on the same
+/// commit, the first one encountered is used.
+///
+/// # Example
+///
+/// ```ignore
+/// use git2::Repository;
+/// use git2version::get_git_info;
+///
+/// let repo = Repository::discover(".").unwrap();
+/// let info = get_git_info(&repo).unwrap();
+/// println!("Commit: {}", info.commit_id);
+/// if let Some(tag_info) = info.tag_info {
+///     println!("Tag: {} (+{} commits)", tag_info.tag, tag_info.commits_since_tag);
+/// }
+/// ```
+pub fn get_git_info(repo: &Repository) -> Result<GitInfoOwned, git2::Error> {
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let head_commit_id_str = head_commit.id().to_string();
+    let head_commit_id_str = head_commit_id_str[..COMMIT_ID_SHORT_HASH_LENGTH].to_string();
+
+    // A bare repository doesn't have a working directory that could have modifications
+    let modified = !repo.is_bare() && {
+        let statuses = repo.statuses(Some(
+            StatusOptions::default()
+                .show(StatusShow::IndexAndWorkdir)
+                .include_untracked(false)
+                .include_ignored(false)
+                .include_unmodified(false)
+                .exclude_submodules(false),
+        ))?;
+        statuses.iter().any(|status| {
+            status.status() != git2::Status::CURRENT && status.status() != git2::Status::IGNORED
+        })
+    };
+
+    let worktree = worktree_name(repo)?;
+
+    // find closest ancestor tag, only looking at first parents (i.e. ignoring merge commits)
+    // We do this without using `git describe` because the `git describe` format can be ambigious
+    // if the version number contains dashes
+    let all_tags = git_helpers::all_tags(repo)?;
+    let mut current_commit = head_commit;
+    let mut commits_since_tag = 0;
+    loop {
+        let commit_id = current_commit.id();
+        if let Some(tags) = all_tags.get(&commit_id) {
+            // TODO Don't just take the first tag, but compare version numbers
+            let tag = tags.first().expect(
+                "tag list can't be empty, because the `all_tags` HashMap only contains entries that have at least one element",
+            );
+            return Ok(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: tag.to_string(),
+                    commits_since_tag,
+                }),
+                commit_id: head_commit_id_str,
+                modified,
+                source: VersionSource::Git,
+                worktree,
+            });
+        }
+        match current_commit.parent(0) {
+            Ok(parent) => current_commit = parent,
+            Err(_) => {
+                // We reached the root commit without finding a tag
+                return Ok(GitInfoOwned {
+                    tag_info: None,
+                    commit_id: head_commit_id_str,
+                    modified,
+                    source: VersionSource::Git,
+                    worktree,
+                });
+            }
+        }
+        commits_since_tag += 1;
+    }
+}
+
+/// Returns the name of the linked worktree `repo` was opened from, or `None` for the main worktree.
+fn worktree_name(repo: &Repository) -> Result<Option<String>, git2::Error> {
+    if !repo.is_worktree() {
+        return Ok(None);
+    }
+    let worktree = git2::Worktree::open_from_repository(repo)?;
+    let name = worktree
+        .name()
+        .ok_or_else(|| git2::Error::from_str("Worktree name isn't valid UTF-8"))?;
+    Ok(Some(name.to_string()))
+}
+
+/// Retrieves the version information of all submodules of the given repository, sorted by path.
+///
+/// For each submodule, [`get_git_info`] is called on the repository checked out at its path, so the
+/// result reflects the checked out commit and whether the submodule's working directory has modifications.
+/// Submodules that aren't initialized have `None` as their [`git_info`](SubmoduleInfoOwned::git_info).
+///
+/// Only direct submodules are listed, submodules of submodules aren't.
+///
+/// # Errors
+///
+/// Returns a [`git2::Error`] if the submodules can't be listed, e.g. because `.gitmodules` is invalid,
+/// or if a submodule name or path isn't valid UTF-8.
+///
+/// # Example
+///
+/// ```ignore
+/// use git2::Repository;
+/// use git2version::get_submodule_info;
+///
+/// let repo = Repository::discover(".").unwrap();
+/// for submodule in get_submodule_info(&repo).unwrap() {
+///     println!("{submodule}");
+/// }
+/// ```
+pub fn get_submodule_info(repo: &Repository) -> Result<Vec<SubmoduleInfoOwned>, git2::Error> {
+    let mut result = repo
+        .submodules()?
+        .iter()
+        .map(|submodule| {
+            let name = submodule
+                .name()
+                .ok_or_else(|| git2::Error::from_str("Submodule name isn't valid UTF-8"))?;
+            let path = submodule
+                .path()
+                .to_str()
+                .ok_or_else(|| git2::Error::from_str("Submodule path isn't valid UTF-8"))?;
+            // Opening fails if the submodule isn't initialized
+            let git_info = submodule
+                .open()
+                .ok()
+                .and_then(|submodule_repo| get_git_info(&submodule_repo).ok());
+            Ok(SubmoduleInfoOwned {
+                name: name.to_string(),
+                path: path.to_string(),
+                git_info,
+            })
+        })
+        .collect::<Result<Vec<_>, git2::Error>>()?;
+    result.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn create_repo(path: &std::path::Path) -> Repository {
+        let repo = Repository::init(path).unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.name", "Test User")
+            .unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.email", "test@example.com")
+            .unwrap();
+        repo
+    }
+
+    fn create_initial_commit(repo: &Repository) -> git2::Oid {
+        let content = "initial content";
+        std::fs::write(repo.workdir().unwrap().join("file.txt"), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+
+        let sig = repo.signature().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap()
+    }
+
+    fn create_commit(repo: &Repository, content: &str) -> git2::Oid {
+        std::fs::write(repo.workdir().unwrap().join("file.txt"), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+
+        let sig = repo.signature().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            &format!("Commit: {}", content),
+            &tree,
+            &[&head_commit],
+        )
+        .unwrap()
+    }
+
+    fn create_tag(repo: &Repository, tag_name: &str) {
+        let head_commit = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+        repo.tag_lightweight(tag_name, &head_commit, true).unwrap();
+    }
+
+    fn add_to_index(repo: &Repository) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+    }
+
+    #[test]
+    fn commit_id_has_correct_length() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        let info = get_git_info(&repo).unwrap();
+        assert_eq!(info.commit_id.len(), COMMIT_ID_SHORT_HASH_LENGTH);
+    }
+
+    #[test]
+    fn no_tags_clean_workdir() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(info.tag_info.is_none());
+        assert!(!info.modified);
+    }
+
+    #[test]
+    fn no_tags_dirty_workdir() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        // Modify a tracked file
+        std::fs::write(repo.workdir().unwrap().join("file.txt"), "modified").unwrap();
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(info.tag_info.is_none());
+        assert!(info.modified);
+    }
+
+    #[test]
+    fn on_tag_not_modified() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        create_tag(&repo, "v1.0.0");
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(info.tag_info.is_some());
+        let tag_info = info.tag_info.unwrap();
+        assert_eq!(tag_info.tag, "v1.0.0");
+        assert_eq!(tag_info.commits_since_tag, 0);
+        assert!(!info.modified);
+    }
+
+    #[test]
+    fn commits_after_tag() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        create_tag(&repo, "v1.0.0");
+
+        // Add 3 more commits after the tag
+        create_commit(&repo, "second");
+        create_commit(&repo, "third");
+        create_commit(&repo, "fourth");
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(info.tag_info.is_some());
+        let tag_info = info.tag_info.unwrap();
+        assert_eq!(tag_info.tag, "v1.0.0");
+        assert_eq!(tag_info.commits_since_tag, 3);
+    }
+
+    #[test]
+    fn untracked_files_not_counted_as_modified() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        // Add a new untracked file
+        std::fs::write(repo.workdir().unwrap().join("untracked.txt"), "new file").unwrap();
+
+        let info = get_git_info(&repo).unwrap();
+        // Untracked files should NOT be considered modifications
+        assert!(!info.modified);
+    }
+
+    #[test]
+    fn staged_changes_count_as_modified() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        // Modify a tracked file and stage it
+        std::fs::write(repo.workdir().unwrap().join("file.txt"), "staged changes").unwrap();
+        add_to_index(&repo);
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(info.modified);
+    }
+
+    #[test]
+    fn empty_repo_returns_error() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+
+        // Empty repo has no HEAD, should error
+        let result = get_git_info(&repo);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn commit_id_is_prefix_of_full_hash() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        let full_oid = create_initial_commit(&repo);
+        let full_hash = full_oid.to_string();
+
+        let info = get_git_info(&repo).unwrap();
+        assert!(full_hash.starts_with(&info.commit_id));
+    }
+
+    fn add_submodule(repo: &Repository, url: &std::path::Path, path: &str) {
+        let mut submodule = repo
+            .submodule(url.to_str().unwrap(), std::path::Path::new(path), true)
+            .unwrap();
+        submodule.clone(None).unwrap();
+        submodule.add_finalize().unwrap();
+    }
+
+    #[test]
+    fn no_submodules() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        assert_eq!(
+            Vec::<SubmoduleInfoOwned>::new(),
+            get_submodule_info(&repo).unwrap()
+        );
+    }
+
+    #[test]
+    fn submodules() {
+        let lib_dir = TempDir::new("test").unwrap();
+        let lib_repo = create_repo(lib_dir.path());
+        create_initial_commit(&lib_repo);
+        create_tag(&lib_repo, "v2.0.0");
+        create_commit(&lib_repo, "second");
+
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        add_submodule(&repo, lib_dir.path(), "vendor/lib");
+        add_submodule(&repo, lib_dir.path(), "another_lib");
+
+        let submodules = get_submodule_info(&repo).unwrap();
+        assert_eq!(2, submodules.len());
+        assert_eq!("another_lib", submodules[0].path);
+        assert_eq!("vendor/lib", submodules[1].name);
+        assert_eq!("vendor/lib", submodules[1].path);
+        let git_info = submodules[1].git_info.as_ref().unwrap();
+        assert_eq!("v2.0.0", git_info.tag_info.as_ref().unwrap().tag);
+        assert_eq!(1, git_info.tag_info.as_ref().unwrap().commits_since_tag);
+        assert!(!git_info.modified);
+        assert_eq!(
+            get_git_info(&lib_repo).unwrap().commit_id,
+            git_info.commit_id
+        );
+
+        // Modifications inside of the submodule are reported for the submodule
+        std::fs::write(dir.path().join("vendor/lib/file.txt"), "modified").unwrap();
+        let submodules = get_submodule_info(&repo).unwrap();
+        assert!(submodules[1].git_info.as_ref().unwrap().modified);
+        assert!(!submodules[0].git_info.as_ref().unwrap().modified);
+    }
+
+    #[test]
+    fn uninitialized_submodule() {
+        let lib_dir = TempDir::new("test").unwrap();
+        let lib_repo = create_repo(lib_dir.path());
+        create_initial_commit(&lib_repo);
+
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        add_submodule(&repo, lib_dir.path(), "lib");
+        std::fs::remove_dir_all(dir.path().join("lib")).unwrap();
+
+        let submodules = get_submodule_info(&repo).unwrap();
+        assert_eq!(1, submodules.len());
+        assert_eq!("lib", submodules[0].name);
+        assert_eq!(None, submodules[0].git_info);
+    }
+
+    #[test]
+    fn main_worktree() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+
+        assert_eq!(None, get_git_info(&repo).unwrap().worktree);
+    }
+
+    #[test]
+    fn linked_worktree() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        create_tag(&repo, "v1.0.0");
+        let worktree_dir = TempDir::new("test").unwrap();
+        let worktree_path = worktree_dir.path().join("feature");
+        let worktree = repo.worktree("feature", &worktree_path, None).unwrap();
+        let worktree_repo = Repository::open_from_worktree(&worktree).unwrap();
+
+        let info = get_git_info(&worktree_repo).unwrap();
+        assert_eq!(Some("feature"), info.worktree.as_deref());
+        assert_eq!(get_git_info(&repo).unwrap().commit_id, info.commit_id);
+        assert_eq!("v1.0.0", info.tag_info.unwrap().tag);
+        assert!(!info.modified);
+        assert!(!get_git_info(&repo).unwrap().modified);
+        std::fs::write(worktree_path.join("file.txt"), "modified").unwrap();
+        assert!(get_git_info(&worktree_repo).unwrap().modified);
+        assert!(!get_git_info(&repo).unwrap().modified);
+
+        // Discovering the repository from the worktree's directory finds the worktree as well
+        let discovered = Repository::discover(&worktree_path).unwrap();
+        assert_eq!(
+            Some("feature"),
+            get_git_info(&discovered).unwrap().worktree.as_deref()
+        );
+    }
+
+    #[test]
+    fn bare_repository() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        create_initial_commit(&repo);
+        create_tag(&repo, "v1.0.0");
+        create_commit(&repo, "second");
+        // Uncommitted changes in the original repository don't affect the bare clone
+        std::fs::write(dir.path().join("file.txt"), "modified").unwrap();
+
+        let bare_dir = TempDir::new("test").unwrap();
+        let bare_repo = git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(dir.path().to_str().unwrap(), bare_dir.path())
+            .unwrap();
+        assert!(bare_repo.workdir().is_none());
+
+        let info = get_git_info(&bare_repo).unwrap();
+        assert_eq!(
+            GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v1.0.0".to_string(),
+                    commits_since_tag: 1,
+                }),
+                modified: false,
+                ..get_git_info(&repo).unwrap()
+            },
+            info
+        );
+    }
+}
@@ -1,5 +1,98 @@
-use git2::{Oid, Repository};
+use git2::{Oid, Repository, RepositoryOpenFlags};
 use std::collections::hash_map::{Entry, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Environment variables that [`open_repository_from_env`] honors.
+pub const GIT_ENV_VARS: [&str; 8] = [
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_CEILING_DIRECTORIES",
+    "GIT_DISCOVERY_ACROSS_FILESYSTEM",
+    "GIT_INDEX_FILE",
+    "GIT_NAMESPACE",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+];
+
+/// Opens the git repository containing `dir` the same way `git` would if it was run in `dir`.
+///
+/// Unlike [`Repository::discover`], this honors the [`GIT_ENV_VARS`]. In particular, if `GIT_DIR` is set,
+/// the repository at `GIT_DIR` is opened instead of searching for one in `dir` and its parents,
+/// and `GIT_WORK_TREE` overrides the working directory. Relative paths in these variables are relative
+/// to the current directory of the process, which for build scripts is the directory of the crate's `Cargo.toml`.
+pub fn open_repository_from_env(dir: &Path) -> Result<Repository, git2::Error> {
+    if std::env::var_os("GIT_DIR").is_some() {
+        // libgit2 only looks at `GIT_DIR` if it isn't given a path to start searching from
+        Repository::open_from_env()
+    } else {
+        Repository::open_ext(
+            dir,
+            RepositoryOpenFlags::FROM_ENV,
+            std::iter::empty::<&OsStr>(),
+        )
+    }
+}
+
+/// Returns the first of `ceiling_dirs` that `git` wouldn't have crossed to discover `repo` from `start_dir`, if any.
+///
+/// This follows the semantics of `GIT_CEILING_DIRECTORIES`: discovery doesn't go up into a ceiling directory,
+/// so a repository in a ceiling directory or one of its parents is only found if `start_dir` is the ceiling directory itself.
+/// Relative paths are relative to the current directory of the process.
+pub fn crossed_ceiling_dir<'a>(
+    repo: &Repository,
+    start_dir: &Path,
+    ceiling_dirs: &'a [PathBuf],
+) -> Option<&'a Path> {
+    let repo_root = canonicalize(repo.workdir().unwrap_or_else(|| repo.path()));
+    let start_dir = canonicalize(start_dir);
+    ceiling_dirs
+        .iter()
+        .map(PathBuf::as_path)
+        .find(|ceiling_dir| {
+            let ceiling_dir = canonicalize(ceiling_dir);
+            start_dir.starts_with(&ceiling_dir)
+                && start_dir != ceiling_dir
+                && ceiling_dir.starts_with(&repo_root)
+        })
+}
+
+/// Returns the directories listed in `GIT_CEILING_DIRECTORIES`.
+///
+/// libgit2 still finds a repository in a ceiling directory when searching from below it, while git doesn't,
+/// so we check them with [`crossed_ceiling_dir`] ourselves. Like git, we ignore relative paths.
+pub fn ceiling_dirs_from_env() -> Vec<PathBuf> {
+    std::env::var_os("GIT_CEILING_DIRECTORIES")
+        .map(|ceiling_dirs| {
+            std::env::split_paths(&ceiling_dirs)
+                .filter(|ceiling_dir| ceiling_dir.is_absolute())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns whether the index of `repo` contains at least one file in `dir` or its subdirectories.
+///
+/// Repositories without a working directory, and directories outside of the working directory, don't track any files.
+pub fn tracks_files_in(repo: &Repository, dir: &Path) -> Result<bool, git2::Error> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(false);
+    };
+    let dir = canonicalize(dir);
+    let Ok(relative_dir) = dir.strip_prefix(canonicalize(workdir)) else {
+        return Ok(false);
+    };
+    let index = repo.index()?;
+    let tracked = index.iter().any(|entry| {
+        std::str::from_utf8(&entry.path).is_ok_and(|path| Path::new(path).starts_with(relative_dir))
+    });
+    Ok(tracked)
+}
+
+/// libgit2 resolves symlinks in the paths of a repository, so we need to do the same before comparing paths with them.
+fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
 
 /// Retrieves all tags from a repository, grouped by the commit they point to.
 ///
@@ -242,4 +335,76 @@ mod tests {
         let tags = all_tags(&repo).unwrap();
         assert_eq!(tags[&commit_oid], vec!["v1.0.0-beta.1_test"]);
     }
+
+    #[test]
+    fn ceiling_dir_below_repository_root() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        let start_dir = dir.path().join("vendor/crate");
+        std::fs::create_dir_all(&start_dir).unwrap();
+
+        let ceiling_dirs = [dir.path().join("vendor")];
+        assert_eq!(
+            Some(ceiling_dirs[0].as_path()),
+            crossed_ceiling_dir(&repo, &start_dir, &ceiling_dirs)
+        );
+        // Like git, a repository in the ceiling directory itself is ignored as well
+        let ceiling_dirs = [dir.path().to_path_buf()];
+        assert_eq!(
+            Some(ceiling_dirs[0].as_path()),
+            crossed_ceiling_dir(&repo, &start_dir, &ceiling_dirs)
+        );
+    }
+
+    #[test]
+    fn ceiling_dir_not_crossed() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(&dir.path().join("repo"));
+        let start_dir = dir.path().join("repo/crate");
+        std::fs::create_dir_all(&start_dir).unwrap();
+
+        // The repository is below the ceiling directory
+        assert_eq!(
+            None,
+            crossed_ceiling_dir(&repo, &start_dir, &[dir.path().to_path_buf()])
+        );
+        // Starting in the ceiling directory itself doesn't cross it
+        assert_eq!(
+            None,
+            crossed_ceiling_dir(&repo, &start_dir, std::slice::from_ref(&start_dir))
+        );
+        // The ceiling directory is somewhere else
+        assert_eq!(
+            None,
+            crossed_ceiling_dir(&repo, &start_dir, &[dir.path().join("other")])
+        );
+        assert_eq!(None, crossed_ceiling_dir(&repo, &start_dir, &[]));
+    }
+
+    #[test]
+    fn tracks_files() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        std::fs::create_dir_all(dir.path().join("tracked")).unwrap();
+        std::fs::create_dir_all(dir.path().join("untracked")).unwrap();
+        std::fs::write(dir.path().join("tracked/Cargo.toml"), "").unwrap();
+        std::fs::write(dir.path().join("untracked/Cargo.toml"), "").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked/Cargo.toml")).unwrap();
+        index.write().unwrap();
+
+        assert!(tracks_files_in(&repo, dir.path()).unwrap());
+        assert!(tracks_files_in(&repo, &dir.path().join("tracked")).unwrap());
+        assert!(!tracks_files_in(&repo, &dir.path().join("untracked")).unwrap());
+        // `tracked_other` has `tracked` as a string prefix, but isn't inside of it
+        std::fs::create_dir_all(dir.path().join("tracked_other")).unwrap();
+        assert!(!tracks_files_in(&repo, &dir.path().join("tracked_other")).unwrap());
+    }
+
+    #[test]
+    fn empty_repo_doesnt_track_files() {
+        let dir = TempDir::new("test").unwrap();
+        let repo = create_repo(dir.path());
+        assert!(!tracks_files_in(&repo, dir.path()).unwrap());
+    }
 }
@@ -1,5 +1,6 @@
+use core::fmt::{self, Debug, Display, Formatter};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Debug, Display, Formatter};
 
 /// Information about a git tag that is an ancestor of the current commit.
 ///
@@ -18,7 +19,8 @@ use std::fmt::{self, Debug, Display, Formatter};
 /// assert_eq!(tag_info.tag, "v1.2.3");
 /// assert_eq!(tag_info.commits_since_tag, 5);
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TagInfo<'a> {
     /// The name of the tag (e.g., `"v1.2.3"`, `"release-1.0"`).
     pub tag: &'a str,
@@ -28,6 +30,87 @@ pub struct TagInfo<'a> {
     pub commits_since_tag: u32,
 }
 
+/// Where the version information in a [`GitInfo`] came from.
+///
+/// # Example
+///
+/// ```
+/// use git2version::VersionSource;
+///
+/// assert_eq!(VersionSource::Git.name(), "git");
+/// assert_eq!(VersionSource::from_name("cargo-manifest"), Some(VersionSource::CargoManifest));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum VersionSource {
+    /// The version information was read from the git repository.
+    #[default]
+    Git,
+
+    /// The version information was (at least partially) set through `GIT2VERSION_*` environment variables.
+    /// See [`init_proxy_build!`](crate::init_proxy_build) for details.
+    Environment,
+
+    /// There was no git repository and the version information was read from a snapshot file.
+    /// See [`init_proxy_build!`](crate::init_proxy_build) for details.
+    SnapshotFile,
+
+    /// There was no git repository and the version was taken from the `version` field of a `Cargo.toml`.
+    ///
+    /// In this case, [`GitInfo::tag_info`] contains the cargo package version as tag with `0` commits since tag,
+    /// [`GitInfo::commit_id`] is empty and [`GitInfo::modified`] is `false`.
+    CargoManifest,
+}
+
+impl VersionSource {
+    /// All version sources.
+    pub const ALL: [Self; 4] = [
+        Self::Git,
+        Self::Environment,
+        Self::SnapshotFile,
+        Self::CargoManifest,
+    ];
+
+    /// Returns a short name for the version source, e.g. `"git"` or `"cargo-manifest"`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::Environment => "environment",
+            Self::SnapshotFile => "snapshot-file",
+            Self::CargoManifest => "cargo-manifest",
+        }
+    }
+
+    /// Inverse of [`VersionSource::name`]. Returns `None` if `name` isn't a known version source.
+    pub const fn from_name(name: &str) -> Option<Self> {
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            if const_str_eq(name, Self::ALL[i].name()) {
+                return Some(Self::ALL[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// `str` equality for const contexts, where `==` isn't available.
+const fn const_str_eq(lhs: &str, rhs: &str) -> bool {
+    let (lhs, rhs) = (lhs.as_bytes(), rhs.as_bytes());
+    if lhs.len() != rhs.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < lhs.len() {
+        if lhs[i] != rhs[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 /// Git version information extracted from a repository.
 ///
 /// This struct contains information about the current commit, including
@@ -45,10 +128,17 @@ pub struct TagInfo<'a> {
 /// - `g{commit_id}` is the shortened commit hash prefixed with `'g'` (for "git")
 /// - `.modified` is appended if the working directory has uncommitted changes
 ///
+/// If the version information didn't come from git, the [name](VersionSource::name) of its
+/// [`source`](GitInfo::source) is appended, e.g. `v1.2.3+0.gabcdef1234.environment`.
+/// If the commit id is empty, e.g. because the version was taken from a `Cargo.toml`
+/// (see [`VersionSource::CargoManifest`]), the `.g{commit_id}` part is omitted, e.g. `1.2.3+0.cargo-manifest`.
+///
+/// Other formats, e.g. a `git describe` compatible or a SemVer version, are available through [`GitInfo::display_as`].
+///
 /// # Examples
 ///
 /// ```
-/// use git2version::{GitInfo, TagInfo};
+/// use git2version::{GitInfo, TagInfo, VersionSource};
 ///
 /// // Version on a tag, clean working directory
 /// let on_tag = GitInfo {
@@ -58,6 +148,8 @@ pub struct TagInfo<'a> {
 ///     }),
 ///     commit_id: "abcdef1234",
 ///     modified: false,
+///     source: VersionSource::Git,
+///     worktree: None,
 /// };
 /// assert_eq!(format!("{}", on_tag), "v1.2.3+0.gabcdef1234");
 ///
@@ -69,6 +161,8 @@ pub struct TagInfo<'a> {
 ///     }),
 ///     commit_id: "abcdef1234",
 ///     modified: true,
+///     source: VersionSource::Git,
+///     worktree: None,
 /// };
 /// assert_eq!(format!("{}", after_tag_modified), "v1.2.3+5.gabcdef1234.modified");
 ///
@@ -77,11 +171,14 @@ pub struct TagInfo<'a> {
 ///     tag_info: None,
 ///     commit_id: "abcdef1234",
 ///     modified: false,
+///     source: VersionSource::Git,
+///     worktree: None,
 /// };
 /// assert_eq!(format!("{}", no_tag), "unknown.gabcdef1234");
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(bound(deserialize = "'de: 'a"))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct GitInfo<'a, 'b> {
     /// Information on the tag that is the closest ancestor tag to the current commit.
     ///
@@ -100,6 +197,17 @@ pub struct GitInfo<'a, 'b> {
     /// This is `true` if there are staged or unstaged changes to tracked files.
     /// Untracked files are not considered modifications.
     pub modified: bool,
+
+    /// Where this version information came from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source: VersionSource,
+
+    /// The name of the linked worktree (see `git worktree`) that the version information was read from.
+    ///
+    /// This is `None` in the main worktree and if the version information didn't come from git.
+    /// It isn't part of the [`Display`] format, so builds from different worktrees of the same commit report the same version.
+    #[cfg_attr(feature = "serde", serde(default, borrow))]
+    pub worktree: Option<&'b str>,
 }
 
 impl<'a, 'b> Debug for GitInfo<'a, 'b> {
@@ -115,18 +223,98 @@ impl<'a, 'b> Display for GitInfo<'a, 'b> {
         } else {
             write!(f, "unknown")?;
         }
-        write!(f, ".g{}", self.commit_id)?;
+        if !self.commit_id.is_empty() {
+            write!(f, ".g{}", self.commit_id)?;
+        }
         if self.modified {
             write!(f, ".modified")?;
         }
+        if self.source != VersionSource::Git {
+            write!(f, ".{}", self.source.name())?;
+        }
         Ok(())
     }
 }
 
+/// Version information of a git submodule.
+///
+/// If `ProxyBuildConfig::submodules` is enabled, [`init_proxy_lib!`](crate::init_proxy_lib) generates
+/// a `SUBMODULES` constant with one entry per submodule of the repository.
+///
+/// # Example
+///
+/// ```
+/// use git2version::{GitInfo, SubmoduleInfo, VersionSource};
+///
+/// let submodule = SubmoduleInfo {
+///     name: "vendor/zlib",
+///     path: "vendor/zlib",
+///     git_info: Some(GitInfo {
+///         tag_info: None,
+///         commit_id: "abcdef1234",
+///         modified: false,
+///         source: VersionSource::Git,
+///         worktree: None,
+///     }),
+/// };
+/// assert_eq!(submodule.to_string(), "vendor/zlib: unknown.gabcdef1234");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct SubmoduleInfo<'a> {
+    /// The name of the submodule in `.gitmodules`.
+    pub name: &'a str,
+
+    /// The path of the submodule, relative to the root of the repository containing it.
+    pub path: &'a str,
+
+    /// The version information of the submodule's checked out commit.
+    ///
+    /// This is `None` if the submodule isn't initialized or if there was an error looking up its version information.
+    pub git_info: Option<GitInfo<'a, 'a>>,
+}
+
+/// Formats the submodule as `{path}: {version}`, with `unknown` as version if [`SubmoduleInfo::git_info`] is `None`.
+impl<'a> Display for SubmoduleInfo<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.git_info {
+            Some(git_info) => write!(f, "{}: {git_info}", self.path),
+            None => write!(f, "{}: unknown", self.path),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    mod version_source {
+        use super::*;
+
+        #[test]
+        fn name_roundtrip() {
+            for source in VersionSource::ALL {
+                assert_eq!(Some(source), VersionSource::from_name(source.name()));
+            }
+        }
+
+        #[test]
+        fn unknown_name() {
+            assert_eq!(None, VersionSource::from_name(""));
+            assert_eq!(None, VersionSource::from_name("svn"));
+            assert_eq!(None, VersionSource::from_name("gi"));
+            assert_eq!(None, VersionSource::from_name("gitt"));
+            assert_eq!(None, VersionSource::from_name("Git"));
+        }
+
+        #[test]
+        fn from_name_in_const_context() {
+            const SOURCE: Option<VersionSource> = VersionSource::from_name("snapshot-file");
+            assert_eq!(Some(VersionSource::SnapshotFile), SOURCE);
+        }
+    }
+
     mod display {
         use super::*;
 
@@ -136,6 +324,8 @@ mod tests {
                 tag_info: None,
                 commit_id: "abcdef",
                 modified: false,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("unknown.gabcdef", format!("{}", version));
             assert_eq!("unknown.gabcdef", format!("{:?}", version));
@@ -147,6 +337,8 @@ mod tests {
                 tag_info: None,
                 commit_id: "abcdef",
                 modified: true,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("unknown.gabcdef.modified", format!("{}", version));
             assert_eq!("unknown.gabcdef.modified", format!("{:?}", version));
@@ -161,6 +353,8 @@ mod tests {
                 }),
                 commit_id: "abcdef",
                 modified: false,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("v1.2.3+10.gabcdef", format!("{}", version));
             assert_eq!("v1.2.3+10.gabcdef", format!("{:?}", version));
@@ -175,6 +369,8 @@ mod tests {
                 }),
                 commit_id: "abcdef",
                 modified: true,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("v1.2.3+10.gabcdef.modified", format!("{}", version));
             assert_eq!("v1.2.3+10.gabcdef.modified", format!("{:?}", version));
@@ -189,11 +385,64 @@ mod tests {
                 }),
                 commit_id: "abcdef",
                 modified: false,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("v1.2.3+0.gabcdef", format!("{}", version));
             assert_eq!("v1.2.3+0.gabcdef", format!("{:?}", version));
         }
 
+        #[test]
+        fn cargo_manifest_without_commit_id() {
+            let version = GitInfo {
+                tag_info: Some(TagInfo {
+                    tag: "1.2.3",
+                    commits_since_tag: 0,
+                }),
+                commit_id: "",
+                modified: false,
+                source: VersionSource::CargoManifest,
+                worktree: None,
+            };
+            assert_eq!("1.2.3+0.cargo-manifest", format!("{}", version));
+            assert_eq!("1.2.3+0.cargo-manifest", format!("{:?}", version));
+        }
+
+        #[test]
+        fn environment_modified() {
+            let version = GitInfo {
+                tag_info: Some(TagInfo {
+                    tag: "v1.2.3",
+                    commits_since_tag: 10,
+                }),
+                commit_id: "abcdef",
+                modified: true,
+                source: VersionSource::Environment,
+                worktree: None,
+            };
+            assert_eq!(
+                "v1.2.3+10.gabcdef.modified.environment",
+                format!("{}", version)
+            );
+            assert_eq!(
+                "v1.2.3+10.gabcdef.modified.environment",
+                format!("{:?}", version)
+            );
+        }
+
+        #[test]
+        fn snapshot_file_notag() {
+            let version = GitInfo {
+                tag_info: None,
+                commit_id: "abcdef",
+                modified: false,
+                source: VersionSource::SnapshotFile,
+                worktree: None,
+            };
+            assert_eq!("unknown.gabcdef.snapshot-file", format!("{}", version));
+            assert_eq!("unknown.gabcdef.snapshot-file", format!("{:?}", version));
+        }
+
         #[test]
         fn ontag_modified() {
             let version = GitInfo {
@@ -203,6 +452,8 @@ mod tests {
                 }),
                 commit_id: "abcdef",
                 modified: true,
+                source: VersionSource::Git,
+                worktree: None,
             };
             assert_eq!("v1.2.3+0.gabcdef.modified", format!("{}", version));
             assert_eq!("v1.2.3+0.gabcdef.modified", format!("{:?}", version));
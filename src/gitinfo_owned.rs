@@ -1,14 +1,16 @@
-use git2::{Repository, StatusOptions, StatusShow};
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use super::git_helpers;
+use super::gitinfo::{GitInfo, SubmoduleInfo, TagInfo, VersionSource};
 
-use super::COMMIT_ID_SHORT_HASH_LENGTH;
-
-/// Owned version of [`TagInfo`](crate::TagInfo) containing information about the closest ancestor tag.
+/// Owned version of [`TagInfo`] containing information about the closest ancestor tag.
 ///
-/// This struct is used during build time when owned strings are needed.
-/// For the borrowed version used at runtime, see [`TagInfo`](crate::TagInfo).
-#[derive(Clone, PartialEq, Eq)]
+/// For the borrowed version, see [`TagInfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TagInfoOwned {
     /// The name of the tag (e.g., `"v1.2.3"`, `"release-1.0"`).
     pub tag: String,
@@ -18,13 +20,38 @@ pub struct TagInfoOwned {
     pub commits_since_tag: u32,
 }
 
-/// Owned version of [`GitInfo`](crate::GitInfo) containing git version information.
+/// Owned version of [`GitInfo`] containing git version information.
+///
+/// This struct is used during build time by `get_git_info` when owned strings
+/// are needed. [`init_proxy_build!`](crate::init_proxy_build) then writes it as Rust code constructing a
+/// [`GitInfo`] (with borrowed strings) to a file in `OUT_DIR`, which the
+/// [`init_proxy_lib!`](crate::init_proxy_lib) macro `include!`s.
+///
+/// It is also useful at runtime whenever version information needs to outlive the strings it borrows from,
+/// e.g. after deserializing it or [parsing](core::str::FromStr) it from a string received over the network.
+/// Use [`GitInfoOwned::as_borrowed`] and [`GitInfoOwned::from`] to convert between the two.
+///
+/// # Example
 ///
-/// This struct is used during build time by [`get_git_info`] when owned strings
-/// are needed. The data is then serialized to environment variables by [`init_proxy_build!`](crate::init_proxy_build)
-/// and reconstructed as [`GitInfo`](crate::GitInfo) (with borrowed strings) at compile time
-/// by the [`init_proxy_lib!`](crate::init_proxy_lib) macro.
-#[derive(Clone, PartialEq, Eq)]
+/// ```
+/// use git2version::{GitInfo, GitInfoOwned, TagInfo, VersionSource};
+///
+/// let info = GitInfo {
+///     tag_info: Some(TagInfo {
+///         tag: "v1.2.3",
+///         commits_since_tag: 5,
+///     }),
+///     commit_id: "abcdef1234",
+///     modified: false,
+///     source: VersionSource::Git,
+///     worktree: None,
+/// };
+/// let owned = GitInfoOwned::from(info);
+/// assert_eq!(owned.to_string(), "v1.2.3+5.gabcdef1234");
+/// assert_eq!(owned.as_borrowed(), info);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GitInfoOwned {
     /// Information on the tag that is the closest ancestor tag to the current commit.
     /// This is `None` if the repository has no tags or is a shallow clone where tags
@@ -38,293 +65,244 @@ pub struct GitInfoOwned {
     /// Whether the working directory has uncommitted changes (staged or unstaged).
     /// Untracked files are not considered modifications.
     pub modified: bool,
-}
 
-/// Retrieves git version information from the given repository.
-///
-/// This function is called by the [`init_proxy_build!`](crate::init_proxy_build) macro
-/// in the proxy crate's `build.rs` to extract version information from git.
-///
-/// # Arguments
-///
-/// * `repo` - A reference to an opened git2 [`Repository`]
-///
-/// # Returns
-///
-/// Returns a [`GitInfoOwned`] containing:
-/// - The closest ancestor tag (if any) and commits since that tag
-/// - The shortened HEAD commit ID (10 characters)
-/// - Whether the working directory has modifications
-///
-/// # Errors
-///
-/// Returns a [`git2::Error`] if:
-/// - The repository HEAD cannot be resolved (e.g., empty repository with no commits)
-/// - Git status cannot be retrieved
-/// - Tag names contain non-UTF8 characters
-///
-/// # Tag Resolution
-///
-/// Tags are resolved by walking the first-parent history (ignoring merge commits)
-/// from HEAD until a tagged commit is found. If multiple tags exist on the same
-/// commit, the first one encountered is used.
-///
-/// # Example
-///
-/// ```ignore
-/// use git2::Repository;
-/// use git2version::get_git_info;
-///
-/// let repo = Repository::discover(".").unwrap();
-/// let info = get_git_info(&repo).unwrap();
-/// println!("Commit: {}", info.commit_id);
-/// if let Some(tag_info) = info.tag_info {
-///     println!("Tag: {} (+{} commits)", tag_info.tag, tag_info.commits_since_tag);
-/// }
-/// ```
-pub fn get_git_info(repo: &Repository) -> Result<GitInfoOwned, git2::Error> {
-    let head_commit = repo.head()?.peel_to_commit()?;
-    let head_commit_id_str = head_commit.id().to_string();
-    let head_commit_id_str = head_commit_id_str[..COMMIT_ID_SHORT_HASH_LENGTH].to_string();
+    /// Where this version information came from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source: VersionSource,
 
-    let modified = {
-        let statuses = repo.statuses(Some(
-            StatusOptions::default()
-                .show(StatusShow::IndexAndWorkdir)
-                .include_untracked(false)
-                .include_ignored(false)
-                .include_unmodified(false)
-                .exclude_submodules(false),
-        ))?;
-        statuses.iter().any(|status| {
-            status.status() != git2::Status::CURRENT && status.status() != git2::Status::IGNORED
-        })
-    };
+    /// The name of the linked worktree that the version information was read from, see [`GitInfo::worktree`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub worktree: Option<String>,
+}
 
-    // find closest ancestor tag, only looking at first parents (i.e. ignoring merge commits)
-    // We do this without using `git describe` because the `git describe` format can be ambigious
-    // if the version number contains dashes
-    let all_tags = git_helpers::all_tags(repo)?;
-    let mut current_commit = head_commit;
-    let mut commits_since_tag = 0;
-    loop {
-        let commit_id = current_commit.id();
-        if let Some(tags) = all_tags.get(&commit_id) {
-            // TODO Don't just take the first tag, but compare version numbers
-            let tag = tags.first().expect(
-                "tag list can't be empty, because the `all_tags` HashMap only contains entries that have at least one element",
-            );
-            return Ok(GitInfoOwned {
-                tag_info: Some(TagInfoOwned {
-                    tag: tag.to_string(),
-                    commits_since_tag,
-                }),
-                commit_id: head_commit_id_str,
-                modified,
-            });
+impl TagInfoOwned {
+    /// Returns a [`TagInfo`] borrowing the tag from `self`.
+    pub fn as_borrowed(&self) -> TagInfo<'_> {
+        TagInfo {
+            tag: &self.tag,
+            commits_since_tag: self.commits_since_tag,
         }
-        match current_commit.parent(0) {
-            Ok(parent) => current_commit = parent,
-            Err(_) => {
-                // We reached the root commit without finding a tag
-                return Ok(GitInfoOwned {
-                    tag_info: None,
-                    commit_id: head_commit_id_str,
-                    modified,
-                });
-            }
-        }
-        commits_since_tag += 1;
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempdir::TempDir;
-
-    fn create_repo(path: &std::path::Path) -> Repository {
-        let repo = Repository::init(path).unwrap();
-        repo.config()
-            .unwrap()
-            .set_str("user.name", "Test User")
-            .unwrap();
-        repo.config()
-            .unwrap()
-            .set_str("user.email", "test@example.com")
-            .unwrap();
-        repo
+impl From<TagInfo<'_>> for TagInfoOwned {
+    fn from(tag_info: TagInfo<'_>) -> Self {
+        Self {
+            tag: tag_info.tag.to_string(),
+            commits_since_tag: tag_info.commits_since_tag,
+        }
     }
+}
 
-    fn create_initial_commit(repo: &Repository) -> git2::Oid {
-        let content = "initial content";
-        std::fs::write(repo.workdir().unwrap().join("file.txt"), content).unwrap();
-
-        let mut index = repo.index().unwrap();
-        index
-            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
-            .unwrap();
-        index.write().unwrap();
-
-        let sig = repo.signature().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
-        repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
-            .unwrap()
+impl GitInfoOwned {
+    /// Returns a [`GitInfo`] borrowing the strings from `self`.
+    pub fn as_borrowed(&self) -> GitInfo<'_, '_> {
+        GitInfo {
+            tag_info: self.tag_info.as_ref().map(TagInfoOwned::as_borrowed),
+            commit_id: &self.commit_id,
+            modified: self.modified,
+            source: self.source,
+            worktree: self.worktree.as_deref(),
+        }
     }
 
-    fn create_commit(repo: &Repository, content: &str) -> git2::Oid {
-        std::fs::write(repo.workdir().unwrap().join("file.txt"), content).unwrap();
+    /// Compares this version information with `other` to find out which build is newer,
+    /// see [`GitInfo::compare_version`].
+    pub fn compare_version(&self, other: &GitInfoOwned) -> Option<Ordering> {
+        self.as_borrowed().compare_version(&other.as_borrowed())
+    }
 
-        let mut index = repo.index().unwrap();
-        index
-            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
-            .unwrap();
-        index.write().unwrap();
+    /// Returns `true` if this is a release build, see [`GitInfo::is_release`].
+    pub fn is_release(&self) -> bool {
+        self.as_borrowed().is_release()
+    }
 
-        let sig = repo.signature().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
-        let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
-        repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            &format!("Commit: {}", content),
-            &tree,
-            &[&head_commit],
-        )
-        .unwrap()
+    /// Returns `true` if the tag is a pre-release version number, see [`GitInfo::is_prerelease`].
+    pub fn is_prerelease(&self) -> bool {
+        self.as_borrowed().is_prerelease()
     }
+}
 
-    fn create_tag(repo: &Repository, tag_name: &str) {
-        let head_commit = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
-        repo.tag_lightweight(tag_name, &head_commit, true).unwrap();
+impl From<GitInfo<'_, '_>> for GitInfoOwned {
+    fn from(git_info: GitInfo<'_, '_>) -> Self {
+        Self {
+            tag_info: git_info.tag_info.map(TagInfoOwned::from),
+            commit_id: git_info.commit_id.to_string(),
+            modified: git_info.modified,
+            source: git_info.source,
+            worktree: git_info.worktree.map(str::to_string),
+        }
     }
+}
 
-    fn add_to_index(repo: &Repository) {
-        let mut index = repo.index().unwrap();
-        index
-            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
-            .unwrap();
-        index.write().unwrap();
+/// Formats the version information like [`GitInfo`]'s [`Display`] implementation,
+/// e.g. `v1.2.3+5.gabcdef1234.modified`.
+impl Display for GitInfoOwned {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.as_borrowed(), f)
     }
+}
 
-    #[test]
-    fn commit_id_has_correct_length() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
+/// Owned version of [`SubmoduleInfo`] containing the version information of a git submodule.
+///
+/// For the borrowed version, see [`SubmoduleInfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SubmoduleInfoOwned {
+    /// The name of the submodule in `.gitmodules`.
+    pub name: String,
+
+    /// The path of the submodule, relative to the root of the repository containing it.
+    pub path: String,
+
+    /// The version information of the submodule's checked out commit.
+    /// This is `None` if the submodule isn't initialized or if there was an error looking up its version information.
+    pub git_info: Option<GitInfoOwned>,
+}
 
-        let info = get_git_info(&repo).unwrap();
-        assert_eq!(info.commit_id.len(), COMMIT_ID_SHORT_HASH_LENGTH);
+impl SubmoduleInfoOwned {
+    /// Returns a [`SubmoduleInfo`] borrowing the strings from `self`.
+    pub fn as_borrowed(&self) -> SubmoduleInfo<'_> {
+        SubmoduleInfo {
+            name: &self.name,
+            path: &self.path,
+            git_info: self.git_info.as_ref().map(GitInfoOwned::as_borrowed),
+        }
     }
+}
 
-    #[test]
-    fn no_tags_clean_workdir() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
-
-        let info = get_git_info(&repo).unwrap();
-        assert!(info.tag_info.is_none());
-        assert!(!info.modified);
+impl From<SubmoduleInfo<'_>> for SubmoduleInfoOwned {
+    fn from(submodule_info: SubmoduleInfo<'_>) -> Self {
+        Self {
+            name: submodule_info.name.to_string(),
+            path: submodule_info.path.to_string(),
+            git_info: submodule_info.git_info.map(GitInfoOwned::from),
+        }
     }
+}
 
-    #[test]
-    fn no_tags_dirty_workdir() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
+/// Formats the submodule like [`SubmoduleInfo`]'s [`Display`] implementation, e.g. `vendor/zlib: v1.3+0.gabcdef1234`.
+impl Display for SubmoduleInfoOwned {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.as_borrowed(), f)
+    }
+}
 
-        // Modify a tracked file
-        std::fs::write(repo.workdir().unwrap().join("file.txt"), "modified").unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let info = get_git_info(&repo).unwrap();
-        assert!(info.tag_info.is_none());
-        assert!(info.modified);
+    fn infos() -> Vec<GitInfoOwned> {
+        let mut result = vec![];
+        for tag_info in [
+            None,
+            Some(TagInfoOwned {
+                tag: "v1.2.3".to_string(),
+                commits_since_tag: 5,
+            }),
+        ] {
+            for source in VersionSource::ALL {
+                result.push(GitInfoOwned {
+                    tag_info: tag_info.clone(),
+                    commit_id: "abcdef1234".to_string(),
+                    modified: true,
+                    source,
+                    worktree: None,
+                });
+            }
+        }
+        result
     }
 
     #[test]
-    fn on_tag_not_modified() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
-        create_tag(&repo, "v1.0.0");
-
-        let info = get_git_info(&repo).unwrap();
-        assert!(info.tag_info.is_some());
-        let tag_info = info.tag_info.unwrap();
-        assert_eq!(tag_info.tag, "v1.0.0");
-        assert_eq!(tag_info.commits_since_tag, 0);
-        assert!(!info.modified);
+    fn borrowed_roundtrip() {
+        for info in infos() {
+            assert_eq!(info, GitInfoOwned::from(info.as_borrowed()));
+        }
     }
 
     #[test]
-    fn commits_after_tag() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
-        create_tag(&repo, "v1.0.0");
-
-        // Add 3 more commits after the tag
-        create_commit(&repo, "second");
-        create_commit(&repo, "third");
-        create_commit(&repo, "fourth");
-
-        let info = get_git_info(&repo).unwrap();
-        assert!(info.tag_info.is_some());
-        let tag_info = info.tag_info.unwrap();
-        assert_eq!(tag_info.tag, "v1.0.0");
-        assert_eq!(tag_info.commits_since_tag, 3);
+    fn as_borrowed() {
+        let info = GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::SnapshotFile,
+            worktree: None,
+        };
+        assert_eq!(
+            GitInfo {
+                tag_info: Some(TagInfo {
+                    tag: "v1.2.3",
+                    commits_since_tag: 5,
+                }),
+                commit_id: "abcdef1234",
+                modified: true,
+                source: VersionSource::SnapshotFile,
+                worktree: None,
+            },
+            info.as_borrowed()
+        );
     }
 
     #[test]
-    fn untracked_files_not_counted_as_modified() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
-
-        // Add a new untracked file
-        std::fs::write(repo.workdir().unwrap().join("untracked.txt"), "new file").unwrap();
-
-        let info = get_git_info(&repo).unwrap();
-        // Untracked files should NOT be considered modifications
-        assert!(!info.modified);
+    fn display_matches_borrowed() {
+        for info in infos() {
+            assert_eq!(info.as_borrowed().to_string(), info.to_string());
+        }
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn staged_changes_count_as_modified() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        create_initial_commit(&repo);
-
-        // Modify a tracked file and stage it
-        std::fs::write(repo.workdir().unwrap().join("file.txt"), "staged changes").unwrap();
-        add_to_index(&repo);
-
-        let info = get_git_info(&repo).unwrap();
-        assert!(info.modified);
+    fn serde_roundtrip() {
+        for info in infos() {
+            let json = serde_json::to_string(&info).unwrap();
+            assert_eq!(info, serde_json::from_str(&json).unwrap());
+            // The borrowed version uses the same representation
+            assert_eq!(json, serde_json::to_string(&info.as_borrowed()).unwrap());
+        }
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn empty_repo_returns_error() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-
-        // Empty repo has no HEAD, should error
-        let result = get_git_info(&repo);
-        assert!(result.is_err());
+    fn deserialize_without_source() {
+        // Snapshot files written before `source` was added don't have that field
+        let info: GitInfoOwned = serde_json::from_str(
+            r#"{"tag_info":{"tag":"v1.2.3","commits_since_tag":5},"commit_id":"abcdef1234","modified":false}"#,
+        )
+        .unwrap();
+        assert_eq!(VersionSource::Git, info.source);
     }
 
     #[test]
-    fn commit_id_is_prefix_of_full_hash() {
-        let dir = TempDir::new("test").unwrap();
-        let repo = create_repo(dir.path());
-        let full_oid = create_initial_commit(&repo);
-        let full_hash = full_oid.to_string();
+    fn submodule_borrowed_roundtrip() {
+        for git_info in infos().into_iter().map(Some).chain([None]) {
+            let submodule = SubmoduleInfoOwned {
+                name: "zlib".to_string(),
+                path: "vendor/zlib".to_string(),
+                git_info,
+            };
+            assert_eq!(submodule, SubmoduleInfoOwned::from(submodule.as_borrowed()));
+            assert_eq!(submodule.as_borrowed().to_string(), submodule.to_string());
+        }
+    }
 
-        let info = get_git_info(&repo).unwrap();
-        assert!(full_hash.starts_with(&info.commit_id));
+    #[cfg(feature = "serde")]
+    #[test]
+    fn submodule_serde_roundtrip() {
+        let submodule = SubmoduleInfoOwned {
+            name: "zlib".to_string(),
+            path: "vendor/zlib".to_string(),
+            git_info: infos().into_iter().next(),
+        };
+        let json = serde_json::to_string(&submodule).unwrap();
+        assert_eq!(submodule, serde_json::from_str(&json).unwrap());
+        assert_eq!(
+            json,
+            serde_json::to_string(&submodule.as_borrowed()).unwrap()
+        );
     }
 }
@@ -5,6 +5,15 @@
 //!
 //! To use this, you need to setup a proxy-crate in your workspace.
 //!
+//! The easiest way is to let the [command line tool](#command-line-tool) create it. Run this in the directory of your crate's `Cargo.toml`:
+//!
+//! ```bash
+//! cargo install git2version --features cli
+//! git2version init
+//! ```
+//!
+//! Or set it up manually:
+//!
 //! 1. Add this to your Cargo.toml:
 //!
 //! ```toml
@@ -27,7 +36,7 @@
 //! git2version = "*"
 //!
 //! [build-dependencies]
-//! git2version = "*"
+//! git2version = { version = "*", features = ["build"] }
 //! ```
 //! You can also lock the version of git2version to a specific version instead of using `*`.
 //!
@@ -48,7 +57,7 @@
 //!
 //! The `init_proxy_lib!` macro in your proxy crate will generate something similar to the following:
 //! ```rust
-//! # use git2version::{GitInfo, TagInfo};
+//! # use git2version::{GitInfo, TagInfo, VersionSource};
 //! pub const GITINFO: Option<GitInfo> =
 //!     Some(GitInfo {
 //!       tag_info: Some(TagInfo {
@@ -57,6 +66,8 @@
 //!       }),
 //!       commit_id: "a9ebd080a7",
 //!       modified: false,
+//!       source: VersionSource::Git,
+//!       worktree: None,
 //!     });
 //! ```
 //! This object can be `None` if the crate is not in a git repository or if there was an error looking up the version information from git.
@@ -68,6 +79,47 @@
 //! }
 //! ```
 //!
+//! A proxy crate can also expose the version information of several repositories or components as separately named
+//! constants, see the "Named Proxies" sections of `init_proxy_build!` and `init_proxy_lib!`.
+//!
+//!
+//! ## no_std
+//!
+//! The generated `GITINFO` and version constants only need `core`. To use them on `no_std` targets, disable the default `std` feature
+//! of the regular dependency in your proxy crate and add `#![no_std]` to its `src/lib.rs`. The build-dependency still needs the `build` feature,
+//! which runs on the host.
+//!
+//! ```toml
+//! [dependencies]
+//! git2version = { version = "*", default-features = false }
+//! ```
+//!
+//!
+//! ## Command line tool
+//!
+//! With the `cli` feature, this crate provides a `git2version` command line tool. If the proxy crate's `build.rs` enables
+//! `ProxyBuildConfig::embed_link_section`, it can print the version information a binary was built with, without running it:
+//!
+//! ```bash
+//! cargo install git2version --features cli
+//! git2version read path/to/my-app --format json
+//! ```
+//!
+//! The output format can be `human` (the default), `json`, or `shell`. The latter prints `export GIT2VERSION_*=...` lines
+//! that reproduce the version information when building from a source tarball.
+//!
+//! `git2version describe` prints the version information for a git repository exactly like `init_proxy_build!` would compute it,
+//! e.g. to use the same version for Docker tags or artifact names. It takes the same settings as `ProxyBuildConfig`,
+//! except for submodules and additional repositories, which it doesn't print:
+//!
+//! ```bash
+//! git2version describe path/to/version_proxy --cargo-manifest-fallback .. --version-string-template "{tag}-{short}"
+//! ```
+//!
+//! Its output format can be `display` (the default, the `VERSION_STRING` constant), `json`, or `env` (shell-quoted `KEY='value'` lines).
+//!
+//! `git2version init` sets up a proxy crate as described in [Setup](#setup). It is safe to run it again, it won't overwrite existing files or entries.
+//!
 //!
 //! ## Alternatives
 //!
@@ -101,10 +153,27 @@
 //! If we put this into your main crate, then incremental compilations become basically useless because it needs to re-compile everything
 //! for every change. By putting it into a proxy crate, we only need to re-compile the code in the proxy crate and link your main crate
 //! against it.
+//!
+//!
+//! ## Without a proxy crate
+//!
+//! If you can live with less reliable change detection, the [git2version-macros](https://crates.io/crates/git2version-macros) crate provides a
+//! `git_info!()` proc macro that evaluates the version information when your crate is compiled, without a proxy crate:
+//!
+//! ```ignore
+//! pub const GITINFO: Option<git2version::GitInfo> = git2version_macros::git_info!();
+//! ```
+//!
+//! Since proc macros can't use `cargo:rerun-if-changed`, cargo only re-evaluates it when your crate is recompiled, which `git_info!()` triggers
+//! for new commits, checkouts and staged changes. Unstaged changes to files outside of your crate and new tags may not be noticed.
+//! It also doesn't support the `GIT2VERSION_*` environment variables, snapshot files or `ProxyBuildConfig`.
 
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
-// TODO #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 /// The length of the shortened git commit hash used in [`GitInfo::commit_id`].
 ///
@@ -118,19 +187,56 @@
 /// shortened to `a9ebd080a7` (10 characters).
 pub const COMMIT_ID_SHORT_HASH_LENGTH: usize = 10;
 
+#[cfg(feature = "build")]
+mod cargo_manifest;
+#[cfg(feature = "build")]
+mod env_override;
+#[cfg(feature = "build")]
+mod get_git_info;
 #[cfg(feature = "build")]
 mod git_helpers;
 #[cfg(feature = "build")]
-mod gitinfo_owned;
+pub use get_git_info::{get_git_info, get_submodule_info};
+#[cfg(any(feature = "build", feature = "reader"))]
+mod link_section;
+#[cfg(feature = "reader")]
+pub use link_section::{LINK_SECTION_NAME, read_link_section};
 #[cfg(feature = "build")]
-pub use gitinfo_owned::{GitInfoOwned, get_git_info};
+mod proxy_build;
+#[cfg(feature = "build")]
+pub use proxy_build::{ProxyBuildOutput, ProxyBuildResult, compute_proxy_build, run_proxy_build};
+#[cfg(feature = "build")]
+mod proxy_build_config;
+#[cfg(feature = "build")]
+pub use proxy_build_config::{OnVersionMismatch, ProxyBuildConfig};
+#[cfg(feature = "build")]
+mod snapshot;
+#[cfg(feature = "build")]
+pub use snapshot::{SNAPSHOT_FILE_NAME, read_snapshot, write_snapshot};
 
 mod gitinfo;
-pub use gitinfo::{GitInfo, TagInfo};
+pub use gitinfo::{GitInfo, SubmoduleInfo, TagInfo, VersionSource};
+
+#[cfg(feature = "alloc")]
+mod gitinfo_owned;
+#[cfg(feature = "alloc")]
+pub use gitinfo_owned::{GitInfoOwned, SubmoduleInfoOwned, TagInfoOwned};
+
+#[cfg(feature = "alloc")]
+mod parse;
+#[cfg(feature = "alloc")]
+pub use parse::{ParseGitInfoError, ParseGitInfoErrorKind};
+
+mod format;
+pub use format::{DisplayAs, VersionFormat};
+
+mod ordering;
+
+mod template;
+pub use template::{FormatWith, TemplateError, TemplateErrorKind};
 
 mod proxy;
 
 // We need to re-export this because our macros use it
 #[cfg(feature = "build")]
 pub use git2;
-pub use konst;
@@ -0,0 +1,199 @@
+#[cfg(feature = "reader")]
+use std::io;
+#[cfg(feature = "reader")]
+use std::path::Path;
+
+use super::gitinfo_owned::GitInfoOwned;
+
+/// Name of the section that [`init_proxy_lib!`](crate::init_proxy_lib) embeds the version information in
+/// if `ProxyBuildConfig::embed_link_section` is enabled.
+///
+/// This is the ELF section name. In Mach-O binaries, the section is called `__git2version` in the `__DATA` segment.
+#[cfg(feature = "reader")]
+pub const LINK_SECTION_NAME: &str = ".git2version";
+
+#[cfg(feature = "reader")]
+const MACHO_LINK_SECTION_NAME: &str = "__git2version";
+
+/// Every blob in the section starts with this magic number...
+const MAGIC: &[u8; 12] = b"GIT2VERSION\0";
+
+/// ...followed by the format version of the blob...
+const FORMAT_VERSION: u8 = 1;
+
+/// ...three reserved bytes, the length of the payload as little endian `u32`, and the payload itself.
+/// The payload is the version information serialized as JSON, or `null` if the version information wasn't known at build time.
+const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
+
+/// Encodes version information into the blob that gets embedded into the link section.
+#[cfg(feature = "build")]
+pub(crate) fn encode(git_info: Option<&GitInfoOwned>) -> Vec<u8> {
+    let payload = serde_json::to_vec(&git_info).expect("Serializing to JSON can't fail");
+    let payload_len = u32::try_from(payload.len()).expect("Version information too large");
+    let mut blob = Vec::with_capacity(HEADER_LEN + payload.len());
+    blob.extend_from_slice(MAGIC);
+    blob.extend_from_slice(&[FORMAT_VERSION, 0, 0, 0]);
+    blob.extend_from_slice(&payload_len.to_le_bytes());
+    blob.extend_from_slice(&payload);
+    blob
+}
+
+/// Reads the version information that [`init_proxy_lib!`](crate::init_proxy_lib) embedded into the executable or library at `path`.
+///
+/// This only works if the proxy crate's `build.rs` enabled `ProxyBuildConfig::embed_link_section`.
+/// ELF (e.g. Linux) and Mach-O (e.g. macOS) binaries are supported.
+///
+/// Returns `Ok(None)` if the section was embedded, but the version information wasn't known at build time.
+///
+/// # Errors
+///
+/// Returns an [`io::Error`] if the file can't be read, isn't a supported binary format,
+/// or doesn't contain a valid version information section. The latter has [`io::ErrorKind::NotFound`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// let git_info = git2version::read_link_section(Path::new("target/release/my-app")).unwrap();
+/// match git_info {
+///     Some(git_info) => println!("Built from {git_info}"),
+///     None => println!("Built without version information"),
+/// }
+/// ```
+#[cfg(feature = "reader")]
+pub fn read_link_section(path: &Path) -> io::Result<Option<GitInfoOwned>> {
+    use object::{Object, ObjectSection};
+
+    let data = std::fs::read(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Error reading {}: {err}", path.display()),
+        )
+    })?;
+    let file = object::File::parse(&*data).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Error parsing {}: {err}", path.display()),
+        )
+    })?;
+    let section = file
+        .section_by_name(LINK_SECTION_NAME)
+        .or_else(|| file.section_by_name(MACHO_LINK_SECTION_NAME))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} doesn't have a {LINK_SECTION_NAME} section. Was it built with `ProxyBuildConfig::embed_link_section`?",
+                    path.display()
+                ),
+            )
+        })?;
+    let section_data = section
+        .data()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    decode(section_data)
+}
+
+/// Decodes the blob at the start of `section_data`. Anything after the blob, e.g. padding, is ignored.
+#[cfg(feature = "reader")]
+pub(crate) fn decode(section_data: &[u8]) -> io::Result<Option<GitInfoOwned>> {
+    let invalid_data = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+    if section_data.len() < HEADER_LEN || !section_data.starts_with(MAGIC) {
+        return Err(invalid_data("Invalid version information section"));
+    }
+    let format_version = section_data[MAGIC.len()];
+    if format_version != FORMAT_VERSION {
+        return Err(invalid_data(&format!(
+            "Unsupported version information format {format_version}, only format {FORMAT_VERSION} is supported"
+        )));
+    }
+    let payload_len = u32::from_le_bytes(
+        section_data[MAGIC.len() + 4..HEADER_LEN]
+            .try_into()
+            .expect("Slice has length 4"),
+    );
+    let payload = usize::try_from(payload_len)
+        .ok()
+        .and_then(|payload_len| section_data[HEADER_LEN..].get(..payload_len))
+        .ok_or_else(|| invalid_data("Version information section is truncated"))?;
+    Ok(serde_json::from_slice(payload)?)
+}
+
+#[cfg(all(test, feature = "build", feature = "reader"))]
+mod tests {
+    use super::*;
+    use crate::{TagInfoOwned, VersionSource};
+
+    fn info() -> GitInfoOwned {
+        GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::Environment,
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        assert_eq!(Some(info()), decode(&encode(Some(&info()))).unwrap());
+    }
+
+    #[test]
+    fn roundtrip_unknown() {
+        assert_eq!(None, decode(&encode(None)).unwrap());
+    }
+
+    #[test]
+    fn trailing_padding_is_ignored() {
+        let mut blob = encode(Some(&info()));
+        blob.extend_from_slice(&[0; 16]);
+        assert_eq!(Some(info()), decode(&blob).unwrap());
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut blob = encode(Some(&info()));
+        blob[0] = b'X';
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            decode(&blob).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn unsupported_format_version() {
+        let mut blob = encode(Some(&info()));
+        blob[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            decode(&blob).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let blob = encode(Some(&info()));
+        for len in [0, HEADER_LEN - 1, HEADER_LEN, blob.len() - 1] {
+            assert_eq!(
+                io::ErrorKind::InvalidData,
+                decode(&blob[..len]).unwrap_err().kind(),
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn missing_section() {
+        // The test binary itself doesn't have the section
+        let path = std::env::current_exe().unwrap();
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            read_link_section(&path).unwrap_err().kind()
+        );
+    }
+}
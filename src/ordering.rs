@@ -0,0 +1,399 @@
+use core::cmp::Ordering;
+
+use super::format::version_from_tag;
+use super::gitinfo::{GitInfo, VersionSource};
+
+impl<'a, 'b> GitInfo<'a, 'b> {
+    /// Compares this version information with `other` to find out which build is newer.
+    ///
+    /// Versions are compared by their tags first. Tags are compared as [SemVer](https://semver.org) precedence,
+    /// after stripping a leading `v`. Unlike SemVer, the release version can have any number of components
+    /// (e.g. `1.2` or `1.2.3.4`), missing components count as `0`. If the tags are equal, the build with more
+    /// commits since the tag is newer.
+    ///
+    /// Returns `None` if the builds can't be compared, i.e. if
+    /// - either of them was built from a modified working directory,
+    /// - either of them has no tag,
+    /// - the tags are different and at least one of them isn't a version number, or
+    /// - they have the same tag and number of commits since the tag, but different commit ids.
+    ///
+    /// The [`source`](GitInfo::source) of the version information is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use git2version::{GitInfo, TagInfo, VersionSource};
+    ///
+    /// fn info(tag: &'static str, commits_since_tag: u32) -> GitInfo<'static, 'static> {
+    ///     GitInfo {
+    ///         tag_info: Some(TagInfo { tag, commits_since_tag }),
+    ///         commit_id: "abcdef1234",
+    ///         modified: false,
+    ///         source: VersionSource::Git,
+    ///         worktree: None,
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(info("v1.2.3", 5).compare_version(&info("v1.10.0", 0)), Some(Ordering::Less));
+    /// assert_eq!(info("v1.2.3-rc.1", 0).compare_version(&info("v1.2.3", 0)), Some(Ordering::Less));
+    /// assert_eq!(info("v1.2.3", 5).compare_version(&info("v1.2.3", 2)), Some(Ordering::Greater));
+    /// assert_eq!(info("release-foo", 0).compare_version(&info("v1.2.3", 0)), None);
+    /// ```
+    pub fn compare_version(&self, other: &GitInfo<'_, '_>) -> Option<Ordering> {
+        if self.modified || other.modified {
+            return None;
+        }
+        let (self_tag, other_tag) = (self.tag_info?, other.tag_info?);
+        let tag_ordering = if self_tag.tag == other_tag.tag {
+            Ordering::Equal
+        } else {
+            compare_versions(version_from_tag(self)?, version_from_tag(other)?)?
+        };
+        match tag_ordering.then(self_tag.commits_since_tag.cmp(&other_tag.commits_since_tag)) {
+            Ordering::Equal if self.commit_id != other.commit_id => None,
+            ordering => Some(ordering),
+        }
+    }
+
+    /// Returns `true` if this is a release build, i.e. HEAD was exactly on a tag and the working directory wasn't modified.
+    ///
+    /// Version information from a `Cargo.toml` ([`VersionSource::CargoManifest`]) is never a release build. It only has
+    /// the package version as its tag, so we don't know whether the sources were on that tag.
+    pub fn is_release(&self) -> bool {
+        self.source != VersionSource::CargoManifest
+            && !self.modified
+            && self
+                .tag_info
+                .is_some_and(|tag_info| tag_info.commits_since_tag == 0)
+    }
+
+    /// Returns `true` if the tag is a pre-release version number, e.g. `v1.2.3-alpha.1`.
+    ///
+    /// This doesn't say anything about whether HEAD was on the tag, see [`GitInfo::is_release`] for that.
+    pub fn is_prerelease(&self) -> bool {
+        version_from_tag(self)
+            .and_then(Version::parse)
+            .is_some_and(|version| version.pre_release.is_some())
+    }
+}
+
+/// A version number split into its parts. The parts are only validated, not parsed, to avoid allocations.
+struct Version<'a> {
+    /// Dot separated numeric components, e.g. `1.2.3`.
+    release: &'a str,
+
+    /// Dot separated pre-release identifiers, e.g. `alpha.1`.
+    pre_release: Option<&'a str>,
+}
+
+impl<'a> Version<'a> {
+    fn parse(version: &'a str) -> Option<Self> {
+        // Build metadata doesn't affect precedence
+        let version = version
+            .split_once('+')
+            .map_or(version, |(version, _)| version);
+        let (release, pre_release) = match version.split_once('-') {
+            Some((release, pre_release)) => (release, Some(pre_release)),
+            None => (version, None),
+        };
+        if !release.split('.').all(is_numeric) {
+            return None;
+        }
+        if let Some(pre_release) = pre_release {
+            let is_valid_identifier = |identifier: &str| {
+                !identifier.is_empty()
+                    && identifier
+                        .bytes()
+                        .all(|c| c.is_ascii_alphanumeric() || c == b'-')
+            };
+            if !pre_release.split('.').all(is_valid_identifier) {
+                return None;
+            }
+        }
+        Some(Self {
+            release,
+            pre_release,
+        })
+    }
+}
+
+fn compare_versions(lhs: &str, rhs: &str) -> Option<Ordering> {
+    let (lhs, rhs) = (Version::parse(lhs)?, Version::parse(rhs)?);
+    let mut lhs_release = lhs.release.split('.');
+    let mut rhs_release = rhs.release.split('.');
+    loop {
+        let ordering = match (lhs_release.next(), rhs_release.next()) {
+            (None, None) => break,
+            (lhs, rhs) => compare_numeric(lhs.unwrap_or("0"), rhs.unwrap_or("0")),
+        };
+        if ordering != Ordering::Equal {
+            return Some(ordering);
+        }
+    }
+    let ordering = match (lhs.pre_release, rhs.pre_release) {
+        (None, None) => Ordering::Equal,
+        // A pre-release version has lower precedence than the release
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(lhs), Some(rhs)) => {
+            let mut lhs = lhs.split('.');
+            let mut rhs = rhs.split('.');
+            loop {
+                let ordering = match (lhs.next(), rhs.next()) {
+                    (None, None) => break Ordering::Equal,
+                    // A larger set of pre-release identifiers has higher precedence if all preceding ones are equal
+                    (None, Some(_)) => break Ordering::Less,
+                    (Some(_), None) => break Ordering::Greater,
+                    (Some(lhs), Some(rhs)) => match (is_numeric(lhs), is_numeric(rhs)) {
+                        (true, true) => compare_numeric(lhs, rhs),
+                        // Numeric identifiers have lower precedence than alphanumeric ones
+                        (true, false) => Ordering::Less,
+                        (false, true) => Ordering::Greater,
+                        (false, false) => lhs.cmp(rhs),
+                    },
+                };
+                if ordering != Ordering::Equal {
+                    break ordering;
+                }
+            }
+        }
+    };
+    Some(ordering)
+}
+
+fn is_numeric(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// Compares two strings of digits by their numeric value without parsing them, so they can't overflow.
+fn compare_numeric(lhs: &str, rhs: &str) -> Ordering {
+    let lhs = lhs.trim_start_matches('0');
+    let rhs = rhs.trim_start_matches('0');
+    lhs.len().cmp(&rhs.len()).then_with(|| lhs.cmp(rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TagInfo;
+
+    fn info(tag: &'static str, commits_since_tag: u32) -> GitInfo<'static, 'static> {
+        GitInfo {
+            tag_info: Some(TagInfo {
+                tag,
+                commits_since_tag,
+            }),
+            commit_id: "abcdef1234",
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn version_precedence() {
+        // Examples from the SemVer spec, in increasing order
+        let versions = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "2.0.0",
+            "2.1.0",
+            "2.1.1",
+            "2.10.0",
+            "10.0.0",
+        ];
+        for (i, lhs) in versions.iter().enumerate() {
+            for (j, rhs) in versions.iter().enumerate() {
+                assert_eq!(
+                    Some(i.cmp(&j)),
+                    compare_versions(lhs, rhs),
+                    "{lhs} vs {rhs}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn version_equivalence() {
+        for (lhs, rhs) in [
+            ("1.2.3", "1.2.3"),
+            ("1.2", "1.2.0"),
+            ("1.2.3", "1.2.3.0"),
+            ("1.02.3", "1.2.3"),
+            ("1.2.3+build.5", "1.2.3"),
+            ("1.2.3-rc.01", "1.2.3-rc.1"),
+        ] {
+            assert_eq!(
+                Some(Ordering::Equal),
+                compare_versions(lhs, rhs),
+                "{lhs} vs {rhs}"
+            );
+        }
+    }
+
+    #[test]
+    fn huge_version_numbers() {
+        assert_eq!(
+            Some(Ordering::Less),
+            compare_versions("99999999999999999999999", "100000000000000000000000")
+        );
+    }
+
+    #[test]
+    fn invalid_versions() {
+        for version in [
+            "",
+            "foo",
+            "1..2",
+            "1.2.x",
+            "1.2.3-",
+            "1.2.3-alpha..1",
+            "1.2.3-al_pha",
+        ] {
+            assert_eq!(None, compare_versions(version, "1.2.3"), "{version}");
+        }
+    }
+
+    #[test]
+    fn compare_by_tag() {
+        assert_eq!(
+            Some(Ordering::Less),
+            info("v1.2.3", 10).compare_version(&info("v1.2.4", 0))
+        );
+        assert_eq!(
+            Some(Ordering::Greater),
+            info("v2.0.0", 0).compare_version(&info("1.9.9", 10))
+        );
+        assert_eq!(
+            Some(Ordering::Less),
+            info("v2.0.0-rc.1", 3).compare_version(&info("v2.0.0", 0))
+        );
+    }
+
+    #[test]
+    fn compare_by_commits_since_tag() {
+        assert_eq!(
+            Some(Ordering::Less),
+            info("v1.2.3", 2).compare_version(&info("v1.2.3", 5))
+        );
+        assert_eq!(
+            Some(Ordering::Greater),
+            info("release-foo", 5).compare_version(&info("release-foo", 2))
+        );
+        // Different spelling of the same version
+        assert_eq!(
+            Some(Ordering::Greater),
+            info("v1.2.3", 5).compare_version(&info("1.2.3", 2))
+        );
+    }
+
+    #[test]
+    fn compare_equal() {
+        assert_eq!(
+            Some(Ordering::Equal),
+            info("v1.2.3", 5).compare_version(&info("v1.2.3", 5))
+        );
+        let from_cargo_manifest = GitInfo {
+            commit_id: "",
+            source: VersionSource::CargoManifest,
+            worktree: None,
+            ..info("1.2.3", 0)
+        };
+        assert_eq!(
+            Some(Ordering::Equal),
+            from_cargo_manifest.compare_version(&from_cargo_manifest)
+        );
+    }
+
+    #[test]
+    fn incomparable() {
+        let modified = GitInfo {
+            modified: true,
+            ..info("v1.2.3", 5)
+        };
+        let no_tag = GitInfo {
+            tag_info: None,
+            ..info("v1.2.3", 5)
+        };
+        let other_commit = GitInfo {
+            commit_id: "1234abcdef",
+            ..info("v1.2.3", 5)
+        };
+        assert_eq!(None, modified.compare_version(&info("v1.0.0", 0)));
+        assert_eq!(None, info("v2.0.0", 0).compare_version(&modified));
+        assert_eq!(None, modified.compare_version(&modified));
+        assert_eq!(None, no_tag.compare_version(&info("v1.0.0", 0)));
+        assert_eq!(None, info("v1.0.0", 0).compare_version(&no_tag));
+        assert_eq!(None, no_tag.compare_version(&no_tag));
+        assert_eq!(None, other_commit.compare_version(&info("v1.2.3", 5)));
+        assert_eq!(
+            None,
+            info("release-foo", 0).compare_version(&info("v1.2.3", 0))
+        );
+        assert_eq!(
+            None,
+            info("v1.2.3", 0).compare_version(&info("release-foo", 0))
+        );
+    }
+
+    #[test]
+    fn is_release() {
+        assert!(info("v1.2.3", 0).is_release());
+        assert!(info("v1.2.3-rc.1", 0).is_release());
+        assert!(!info("v1.2.3", 1).is_release());
+        assert!(
+            !GitInfo {
+                modified: true,
+                ..info("v1.2.3", 0)
+            }
+            .is_release()
+        );
+        assert!(
+            !GitInfo {
+                tag_info: None,
+                ..info("v1.2.3", 0)
+            }
+            .is_release()
+        );
+    }
+
+    #[test]
+    fn cargo_manifest_is_not_a_release() {
+        let cargo_manifest = GitInfo {
+            commit_id: "",
+            source: VersionSource::CargoManifest,
+            ..info("1.2.3", 0)
+        };
+        assert!(!cargo_manifest.is_release());
+        assert!(
+            GitInfo {
+                source: VersionSource::SnapshotFile,
+                ..info("v1.2.3", 0)
+            }
+            .is_release()
+        );
+    }
+
+    #[test]
+    fn is_prerelease() {
+        assert!(info("v1.2.3-rc.1", 0).is_prerelease());
+        assert!(info("1.2.3-alpha", 5).is_prerelease());
+        assert!(!info("v1.2.3", 0).is_prerelease());
+        assert!(!info("v1.2.3+build-5", 0).is_prerelease());
+        assert!(!info("release-foo", 0).is_prerelease());
+        assert!(
+            !GitInfo {
+                tag_info: None,
+                ..info("v1.2.3", 0)
+            }
+            .is_prerelease()
+        );
+    }
+}
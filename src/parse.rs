@@ -0,0 +1,396 @@
+use alloc::string::ToString;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use super::gitinfo::VersionSource;
+use super::gitinfo_owned::{GitInfoOwned, TagInfoOwned};
+
+/// An error parsing a version string into a [`GitInfoOwned`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseGitInfoError {
+    /// What is wrong with the version string.
+    pub kind: ParseGitInfoErrorKind,
+}
+
+/// The kind of a [`ParseGitInfoError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseGitInfoErrorKind {
+    /// The version string neither starts with `{tag}+{commits}` nor with `unknown`,
+    /// or the tag is empty.
+    InvalidTag,
+
+    /// The number of commits since the tag isn't a valid number.
+    InvalidCommitsSinceTag,
+
+    /// The commit id is empty or isn't a hexadecimal number.
+    InvalidCommitId,
+
+    /// The version string has an unexpected suffix after the tag and commit id.
+    UnexpectedSuffix,
+}
+
+impl Display for ParseGitInfoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            ParseGitInfoErrorKind::InvalidTag => "missing or invalid tag",
+            ParseGitInfoErrorKind::InvalidCommitsSinceTag => "invalid number of commits since tag",
+            ParseGitInfoErrorKind::InvalidCommitId => "invalid commit id",
+            ParseGitInfoErrorKind::UnexpectedSuffix => "unexpected suffix",
+        };
+        write!(f, "Invalid version string: {message}")
+    }
+}
+
+impl core::error::Error for ParseGitInfoError {}
+
+impl FromStr for GitInfoOwned {
+    type Err = ParseGitInfoError;
+
+    /// Parses the [`Display`] format of [`GitInfo`](crate::GitInfo), i.e. `{tag}+{commits}.g{commit_id}[.modified][.{source}]`
+    /// or `unknown.g{commit_id}[.modified][.{source}]`, back into version information.
+    ///
+    /// Tags may contain `+`, `.` and `-`. This is unambiguous because everything after the tag is a number followed by
+    /// dot separated components that never contain a `+`, so the tag ends at the last `+`.
+    ///
+    /// The [`worktree`](GitInfoOwned::worktree) isn't part of the `Display` format, so it is always `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::GitInfoOwned;
+    ///
+    /// let info: GitInfoOwned = "v1.2.3-alpha+build.7+5.gabcdef1234.modified".parse().unwrap();
+    /// let tag_info = info.tag_info.unwrap();
+    /// assert_eq!(tag_info.tag, "v1.2.3-alpha+build.7");
+    /// assert_eq!(tag_info.commits_since_tag, 5);
+    /// assert_eq!(info.commit_id, "abcdef1234");
+    /// assert!(info.modified);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag_info, suffix) = match s.rsplit_once('+') {
+            Some((tag, rest)) => {
+                let (commits_since_tag, suffix) = match rest.find('.') {
+                    Some(index) => rest.split_at(index),
+                    None => (rest, ""),
+                };
+                (Some(tag_info(tag, commits_since_tag)?), suffix)
+            }
+            None => (
+                None,
+                s.strip_prefix("unknown")
+                    .ok_or(error(ParseGitInfoErrorKind::InvalidTag))?,
+            ),
+        };
+
+        let suffix = match suffix {
+            "" => None,
+            suffix => Some(
+                suffix
+                    .strip_prefix('.')
+                    .ok_or(error(ParseGitInfoErrorKind::UnexpectedSuffix))?,
+            ),
+        };
+        let mut components = suffix
+            .into_iter()
+            .flat_map(|suffix| suffix.split('.'))
+            .peekable();
+        let commit_id = match components.next_if(|component| component.starts_with('g')) {
+            Some(component) => commit_id(&component[1..])?,
+            None => "",
+        };
+        let modified = components.next_if_eq(&"modified").is_some();
+        let source = match components.next() {
+            None => VersionSource::Git,
+            // The `Display` format omits the source if it is git
+            Some(name) => VersionSource::from_name(name)
+                .filter(|source| *source != VersionSource::Git)
+                .ok_or(error(ParseGitInfoErrorKind::UnexpectedSuffix))?,
+        };
+        if components.next().is_some() {
+            return Err(error(ParseGitInfoErrorKind::UnexpectedSuffix));
+        }
+
+        Ok(GitInfoOwned {
+            tag_info,
+            commit_id: commit_id.to_string(),
+            modified,
+            source,
+            worktree: None,
+        })
+    }
+}
+
+impl GitInfoOwned {
+    /// Parses the [`VersionFormat::GitDescribe`](crate::VersionFormat::GitDescribe) format,
+    /// i.e. the output of `git describe --tags --long --always --dirty`, back into version information.
+    ///
+    /// The format is `{tag}-{commits}-g{commit_id}[-dirty]`, or `{commit_id}[-dirty]` if there is no tag.
+    /// Tags may contain `-`, `+` and `.`. This is only unambiguous because of `--long`: without it, `git describe`
+    /// outputs just the tag if HEAD is exactly on a tag, which can't be told apart from a commit id.
+    ///
+    /// The `git describe` format doesn't include the [`source`](GitInfoOwned::source), so it is always [`VersionSource::Git`],
+    /// and the [`worktree`](GitInfoOwned::worktree) is always `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::GitInfoOwned;
+    ///
+    /// let info = GitInfoOwned::parse_git_describe("v1.2.3-alpha-5-gabcdef1234-dirty").unwrap();
+    /// let tag_info = info.tag_info.unwrap();
+    /// assert_eq!(tag_info.tag, "v1.2.3-alpha");
+    /// assert_eq!(tag_info.commits_since_tag, 5);
+    /// assert_eq!(info.commit_id, "abcdef1234");
+    /// assert!(info.modified);
+    /// ```
+    pub fn parse_git_describe(s: &str) -> Result<Self, ParseGitInfoError> {
+        let (s, modified) = match s.strip_suffix("-dirty") {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (tag_info, commit_id) = match s.rsplit_once('-') {
+            None => (None, commit_id(s)?),
+            Some((rest, last)) => match last.strip_prefix('g') {
+                Some(commit) => {
+                    let (tag, commits_since_tag) = rest
+                        .rsplit_once('-')
+                        .ok_or(error(ParseGitInfoErrorKind::InvalidTag))?;
+                    (Some(tag_info(tag, commits_since_tag)?), commit_id(commit)?)
+                }
+                // Version information without a commit id, e.g. from a `Cargo.toml`, is formatted without the `-g{commit_id}` part
+                None => (Some(tag_info(rest, last)?), ""),
+            },
+        };
+        Ok(GitInfoOwned {
+            tag_info,
+            commit_id: commit_id.to_string(),
+            modified,
+            source: VersionSource::Git,
+            worktree: None,
+        })
+    }
+}
+
+fn error(kind: ParseGitInfoErrorKind) -> ParseGitInfoError {
+    ParseGitInfoError { kind }
+}
+
+fn tag_info(tag: &str, commits_since_tag: &str) -> Result<TagInfoOwned, ParseGitInfoError> {
+    if tag.is_empty() {
+        return Err(error(ParseGitInfoErrorKind::InvalidTag));
+    }
+    // `u32::from_str` would also accept a leading `+`
+    if !commits_since_tag.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(error(ParseGitInfoErrorKind::InvalidCommitsSinceTag));
+    }
+    let commits_since_tag = commits_since_tag
+        .parse()
+        .map_err(|_| error(ParseGitInfoErrorKind::InvalidCommitsSinceTag))?;
+    Ok(TagInfoOwned {
+        tag: tag.to_string(),
+        commits_since_tag,
+    })
+}
+
+fn commit_id(commit_id: &str) -> Result<&str, ParseGitInfoError> {
+    if commit_id.is_empty() || !commit_id.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error(ParseGitInfoErrorKind::InvalidCommitId));
+    }
+    Ok(commit_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VersionFormat;
+
+    fn all_infos() -> Vec<GitInfoOwned> {
+        let mut result = vec![];
+        for tag in [
+            None,
+            Some(("v1.2.3", 0)),
+            Some(("v1.2.3", 5)),
+            Some(("1.2.3-alpha.1", 5)),
+            Some(("v1.2.3+build.5", 3)),
+            Some(("release-1-2-3", 12)),
+            Some(("unknown", 0)),
+            Some(("deadbeef", 1)),
+            Some(("v1.2.3-5-gabcdef1234", 2)),
+            Some(("v1.2.3+5.gabcdef1234", 2)),
+        ] {
+            for commit_id in ["abcdef1234", ""] {
+                if tag.is_none() && commit_id.is_empty() {
+                    // Can't be represented in the `git describe` format
+                    continue;
+                }
+                for modified in [false, true] {
+                    result.push(GitInfoOwned {
+                        tag_info: tag.map(|(tag, commits_since_tag)| TagInfoOwned {
+                            tag: tag.to_string(),
+                            commits_since_tag,
+                        }),
+                        commit_id: commit_id.to_string(),
+                        modified,
+                        source: VersionSource::Git,
+                        worktree: None,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for info in all_infos() {
+            for source in VersionSource::ALL {
+                let info = GitInfoOwned {
+                    source,
+                    worktree: None,
+                    ..info.clone()
+                };
+                let formatted = info.as_borrowed().to_string();
+                assert_eq!(Ok(&info), formatted.parse().as_ref(), "{formatted}");
+            }
+        }
+    }
+
+    #[test]
+    fn git_describe_roundtrip() {
+        for info in all_infos() {
+            let formatted = info
+                .as_borrowed()
+                .display_as(VersionFormat::GitDescribe)
+                .to_string();
+            assert_eq!(
+                Ok(&info),
+                GitInfoOwned::parse_git_describe(&formatted).as_ref(),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_display() {
+        assert_eq!(
+            Ok(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v1.2.3".to_string(),
+                    commits_since_tag: 5,
+                }),
+                commit_id: "abcdef1234".to_string(),
+                modified: true,
+                source: VersionSource::Environment,
+                worktree: None,
+            }),
+            "v1.2.3+5.gabcdef1234.modified.environment".parse()
+        );
+        assert_eq!(
+            Ok(GitInfoOwned {
+                tag_info: None,
+                commit_id: "abcdef1234".to_string(),
+                modified: false,
+                source: VersionSource::Git,
+                worktree: None,
+            }),
+            "unknown.gabcdef1234".parse()
+        );
+    }
+
+    #[test]
+    fn parse_display_invalid() {
+        for (input, kind) in [
+            ("", ParseGitInfoErrorKind::InvalidTag),
+            ("v1.2.3", ParseGitInfoErrorKind::InvalidTag),
+            ("+5.gabcdef1234", ParseGitInfoErrorKind::InvalidTag),
+            (
+                "v1.2.3+.gabcdef1234",
+                ParseGitInfoErrorKind::InvalidCommitsSinceTag,
+            ),
+            (
+                "v1.2.3+x.gabcdef1234",
+                ParseGitInfoErrorKind::InvalidCommitsSinceTag,
+            ),
+            (
+                "v1.2.3+99999999999",
+                ParseGitInfoErrorKind::InvalidCommitsSinceTag,
+            ),
+            ("v1.2.3+5.g", ParseGitInfoErrorKind::InvalidCommitId),
+            ("v1.2.3+5.gxyz", ParseGitInfoErrorKind::InvalidCommitId),
+            (
+                "v1.2.3+5.gabcdef1234.dirty",
+                ParseGitInfoErrorKind::UnexpectedSuffix,
+            ),
+            (
+                "v1.2.3+5.gabcdef1234.git",
+                ParseGitInfoErrorKind::UnexpectedSuffix,
+            ),
+            (
+                "v1.2.3+5.gabcdef1234.environment.modified",
+                ParseGitInfoErrorKind::UnexpectedSuffix,
+            ),
+            (
+                "v1.2.3+5.gabcdef1234.",
+                ParseGitInfoErrorKind::UnexpectedSuffix,
+            ),
+            ("unknownx", ParseGitInfoErrorKind::UnexpectedSuffix),
+        ] {
+            assert_eq!(
+                Err(ParseGitInfoError { kind }),
+                input.parse::<GitInfoOwned>(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_git_describe() {
+        assert_eq!(
+            Ok(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v1.2.3-rc-1".to_string(),
+                    commits_since_tag: 0,
+                }),
+                commit_id: "abcdef1".to_string(),
+                modified: false,
+                source: VersionSource::Git,
+                worktree: None,
+            }),
+            GitInfoOwned::parse_git_describe("v1.2.3-rc-1-0-gabcdef1")
+        );
+        assert_eq!(
+            Ok(GitInfoOwned {
+                tag_info: None,
+                commit_id: "abcdef1".to_string(),
+                modified: true,
+                source: VersionSource::Git,
+                worktree: None,
+            }),
+            GitInfoOwned::parse_git_describe("abcdef1-dirty")
+        );
+    }
+
+    #[test]
+    fn parse_git_describe_invalid() {
+        for (input, kind) in [
+            ("", ParseGitInfoErrorKind::InvalidCommitId),
+            ("v1.2.3", ParseGitInfoErrorKind::InvalidCommitId),
+            ("-dirty", ParseGitInfoErrorKind::InvalidCommitId),
+            ("5-gabcdef1234", ParseGitInfoErrorKind::InvalidTag),
+            ("-5-gabcdef1234", ParseGitInfoErrorKind::InvalidTag),
+            (
+                "v1.2.3-x-gabcdef1234",
+                ParseGitInfoErrorKind::InvalidCommitsSinceTag,
+            ),
+            ("v1.2.3-rc", ParseGitInfoErrorKind::InvalidCommitsSinceTag),
+            ("v1.2.3-5-gxyz", ParseGitInfoErrorKind::InvalidCommitId),
+            ("v1.2.3-5-g", ParseGitInfoErrorKind::InvalidCommitId),
+        ] {
+            assert_eq!(
+                Err(ParseGitInfoError { kind }),
+                GitInfoOwned::parse_git_describe(input),
+                "{input}"
+            );
+        }
+    }
+}
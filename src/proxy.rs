@@ -3,10 +3,14 @@
 /// This macro should be called in the `build.rs` of your version proxy crate.
 /// It performs the following operations:
 ///
-/// 1. Discovers the git repository containing the proxy crate
-/// 2. Extracts version information (tag, commits since tag, commit ID, modified status)
-/// 3. Sets build environment variables for the `init_proxy_lib!` macro to consume
-/// 4. Configures cargo to rerun when the repository changes
+/// 1. Discovers the git repository containing the proxy crate, honoring `GIT_DIR`, `GIT_WORK_TREE` and the other
+///    environment variables `git` reads to find a repository
+/// 2. Extracts version information (tag, commits since tag, commit ID, modified status),
+///    or reads it from a snapshot file if there is no git repository
+///    and records where it came from as a [`VersionSource`](crate::VersionSource)
+/// 3. Applies overrides from `GIT2VERSION_*` environment variables, if any are set
+/// 4. Generates a `git2version_gitinfo.rs` file in `OUT_DIR` for the `init_proxy_lib!` macro to include
+/// 5. Configures cargo to rerun when the repository changes
 ///
 /// # Usage
 ///
@@ -18,18 +22,141 @@
 /// }
 /// ```
 ///
+/// You can also pass in a `ProxyBuildConfig` to configure its behavior:
+///
+/// ```ignore
+/// fn main() {
+///     git2version::init_proxy_build!(
+///         git2version::ProxyBuildConfig::new().cargo_manifest_fallback("..")
+///     );
+/// }
+/// ```
+///
+/// # Named Proxies
+///
+/// One proxy crate can expose the version information of several repositories or components. Pass a name to each
+/// `init_proxy_build!` call, usually together with `ProxyBuildConfig::repository_dir`, and use the same name in `init_proxy_lib!`:
+///
+/// ```ignore
+/// fn main() {
+///     git2version::init_proxy_build!();
+///     git2version::init_proxy_build!(
+///         "server",
+///         git2version::ProxyBuildConfig::new().repository_dir("../server")
+///     );
+/// }
+/// ```
+///
+/// Names may only contain lowercase ASCII letters, digits and `_`. Each named proxy reads its own environment variable overrides,
+/// e.g. `GIT2VERSION_SERVER__TAG` instead of `GIT2VERSION_TAG`. `ProxyBuildConfig::submodules`,
+/// `ProxyBuildConfig::additional_repository`, `ProxyBuildConfig::version_string_template` and
+/// `ProxyBuildConfig::embed_link_section` only work for the unnamed proxy, the build fails if they're set for a named proxy.
+///
 /// # Rerun Behavior
 ///
 /// The build script will rerun when:
 /// - Any file in the repository working directory changes (to update the `modified` flag)
-/// - Any file in the `.git` directory changes (to detect new tags, commits, fetches, etc.)
+/// - Any file in the `.git` directory changes (to detect new tags, commits, fetches, etc.).
+///   In a linked worktree, this also includes the refs in the main repository's `.git` directory, which all worktrees share.
+/// - Any of the `GIT2VERSION_*` environment variables (of this proxy) changes
+/// - `GIT_DIR`, `GIT_WORK_TREE` or one of the other environment variables that affect repository discovery changes
+/// - Anything in an additional repository or the environment variable containing its path changes
+///
+/// # Bare Repositories
+///
+/// If `GIT_DIR` points to a bare repository and `GIT_WORK_TREE` isn't set, there is no working directory to compare with,
+/// so the `modified` flag is always `false`.
+///
+/// # Discovery Ceiling
+///
+/// The repository is searched for in the proxy crate's directory and its parents, so a crate that is vendored into
+/// an unrelated repository, e.g. a home directory that is tracked with git, would report that repository's version.
+/// `ProxyBuildConfig::ceiling_dir` and `GIT_CEILING_DIRECTORIES` limit how far up the search goes, and with
+/// `ProxyBuildConfig::require_tracked`, a repository is only used if it tracks files in the proxy crate's directory.
+/// If the repository is ignored, the macro continues as if there was no repository.
+///
+/// Git refuses to open repositories owned by a different user unless they're listed in git's `safe.directory` setting.
+/// In that case, the macro emits a warning explaining how to add it and continues as if there was no repository.
+///
+/// # Snapshot File
+///
+/// If no git repository is found, the macro looks for a `.git2version.json`
+/// file next to the `Cargo.toml` of the proxy crate and uses the version information stored in it.
+/// If `ProxyBuildConfig::repository_dir` is set, the file is looked up in that directory instead.
+/// You can create that file with `write_snapshot` before packaging
+/// or vendoring your sources, so that builds from those sources keep reporting an accurate version.
+///
+/// # Environment Variable Overrides
+///
+/// Source tarballs, e.g. created by `cargo package`, don't contain a `.git` directory, so there
+/// is no version information to discover. Distribution packagers can inject the real version
+/// through the following environment variables:
+///
+/// - `GIT2VERSION_TAG` - The tag name. Set it to an empty string to report that there is no tag.
+/// - `GIT2VERSION_COMMITS_SINCE_TAG` - The number of commits since the tag. Defaults to `0`. Requires `GIT2VERSION_TAG`.
+/// - `GIT2VERSION_COMMIT` - The commit id. Full hashes are shortened to [`COMMIT_ID_SHORT_HASH_LENGTH`](crate::COMMIT_ID_SHORT_HASH_LENGTH) characters.
+///   Set it to an empty string if the commit id isn't known, e.g. for a version that came from a `Cargo.toml`.
+/// - `GIT2VERSION_MODIFIED` - Whether the working directory had uncommitted changes (`true` or `false`). Defaults to `false`.
+///
+/// If a git repository or snapshot file was found, each variable that is set takes precedence over the value read from it.
+/// Otherwise, the variables substitute for it, but this requires at least `GIT2VERSION_COMMIT` to be set.
+///
+/// ```bash
+/// GIT2VERSION_TAG=v1.2.3 GIT2VERSION_COMMIT=a9ebd080a7 cargo build
+/// ```
+///
+/// # Cargo Manifest Fallback
+///
+/// If none of the above provided version information and `ProxyBuildConfig::cargo_manifest_fallback`
+/// is configured, the package version from the given crate's `Cargo.toml` is used instead.
+/// The resulting `GITINFO` has [`VersionSource::CargoManifest`](crate::VersionSource::CargoManifest) as its `source`
+/// so consumers can tell that it isn't derived from git.
+///
+/// # Version String Template
+///
+/// With `ProxyBuildConfig::version_string_template`, you can configure
+/// the format of the `VERSION_STRING` constant generated by `init_proxy_lib!`, see [`GitInfo::format_with`](crate::GitInfo::format_with).
+///
+/// # Link Section
+///
+/// With `ProxyBuildConfig::embed_link_section`, `init_proxy_lib!` additionally embeds the version information
+/// into a dedicated section of the compiled binary. It can then be read from the binary file without running it,
+/// e.g. with `read_link_section` or `git2version read` from the command line tool.
+///
+/// # Submodules
+///
+/// With `ProxyBuildConfig::submodules`, the macro also looks up the version information of each submodule
+/// of the repository, which `init_proxy_lib!` makes available as the `SUBMODULES` constant.
+///
+/// # Additional Repositories
+///
+/// With `ProxyBuildConfig::additional_repository`, the macro also looks up the version information of other
+/// repositories that your build depends on, e.g. a sibling checkout. `init_proxy_lib!` generates a constant with the given name for each of them.
+///
+/// ```ignore
+/// fn main() {
+///     git2version::init_proxy_build!(
+///         git2version::ProxyBuildConfig::new()
+///             .additional_repository("SIBLING_GITINFO", "../../sibling")
+///             .additional_repository_from_env("PLUGINS_GITINFO", "PLUGINS_DIR")
+///     );
+/// }
+/// ```
+///
+/// # Cargo Version Check
+///
+/// With `ProxyBuildConfig::cargo_version_check`, the macro compares
+/// the tag with the package version in a given crate's `Cargo.toml` whenever HEAD is exactly on a tag,
+/// and emits a warning or fails the build if they don't match.
 ///
 /// # Errors
 ///
 /// If git information cannot be retrieved (e.g., not in a git repository, empty repo),
-/// the macro emits a cargo warning and sets up the build environment so that `init_proxy_lib!`
+/// the macro emits a cargo warning and generates the code so that `init_proxy_lib!`
 /// will generate a `GITINFO` constant that is `None`.
 ///
+/// If the `GIT2VERSION_*` environment variables have invalid values, the build script panics.
+///
 /// # Requirements
 ///
 /// - Must be called from a `build.rs` script
@@ -44,98 +171,16 @@
 #[macro_export]
 macro_rules! init_proxy_build {
     () => {
-        use $crate::GitInfo;
-
-        let cargo_manifest_dir = env!("CARGO_MANIFEST_DIR");
-
-        fn output_none() {
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_IS_KNOWN=false");
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_HAS_TAG=false");
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_TAG=");
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_COMMITS_SINCE_TAG=");
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_COMMIT_ID=");
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_MODIFIED=");
-        }
-
-        let repo = match $crate::git2::Repository::discover(cargo_manifest_dir) {
-            Ok(repo) => Some(repo),
-            Err(err) => {
-                println!("cargo:warning=Error getting version info from git, didn't find git repository: {}", err);
-                None
-            }
-        };
-        let repository_version = repo.as_ref().and_then(|repo|
-            match $crate::get_git_info(&repo) {
-                Ok(git_info) => Some(git_info),
-                Err(err) => {
-                    println!("cargo:warning=Error getting version info from git: {}", err);
-                    None
-                }
-            }
-        );
-
-        if let Some(repository_version) = repository_version {
-            println!("cargo:rustc-env=PACKAGEVERSION_GITVERSION_IS_KNOWN=true");
-            if let Some(tag_info) = repository_version.tag_info {
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_HAS_TAG=true"
-                );
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_TAG={}",
-                    tag_info.tag
-                );
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_COMMITS_SINCE_TAG={}",
-                    tag_info.commits_since_tag
-                );
-            } else {
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_HAS_TAG=false",
-                );
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_TAG=",
-                );
-                println!(
-                    "cargo:rustc-env=PACKAGEVERSION_GITVERSION_COMMITS_SINCE_TAG=",
-                );
-            }
-            println!(
-                "cargo:rustc-env=PACKAGEVERSION_GITVERSION_COMMIT_ID={}",
-                repository_version.commit_id
-            );
-            println!(
-                "cargo:rustc-env=PACKAGEVERSION_GITVERSION_MODIFIED={}",
-                repository_version.modified
-            );
-        } else {
-            output_none();
-        }
-
-        if let Some(repo) = repo {
-            // Rerun the build script if any files changed. This is necessary to correctly update
-            // the `.modified` flag of version numbers
-            println!(
-                "cargo:rerun-if-changed={repo_workspace_path}",
-                repo_workspace_path = repo.workdir().unwrap().display()
-            );
-
-            // Also rerun the build script if anything in the .git repository changed.
-            // This is for the case where our `Cargo.toml` is in a subdirectory of the
-            // main git repository. In this case, we still need to react to changes in
-            // the git repository.
-            println!(
-                "cargo:rerun-if-changed={repo_path}",
-                repo_path = repo.path().display()
-            );
-        } else {
-            // We didn't find a git repository. Let's rerun if the directory of the `Cargo.toml`
-            // changed to check if a git repository got added. Note: This won't catch cases where
-            // a git repository is added as a parent directory, but probably nothing we can do
-            // about that.
-            println!(
-                "cargo:rerun-if-changed={cargo_manifest_dir}",
-            );
-        }
+        $crate::init_proxy_build!($crate::ProxyBuildConfig::new());
+    };
+    ($name:literal) => {
+        $crate::init_proxy_build!($name, $crate::ProxyBuildConfig::new());
+    };
+    ($name:literal, $config:expr) => {
+        $crate::run_proxy_build(env!("CARGO_MANIFEST_DIR"), Some($name), &$config);
+    };
+    ($config:expr) => {
+        $crate::run_proxy_build(env!("CARGO_MANIFEST_DIR"), None, &$config);
     };
 }
 
@@ -150,8 +195,25 @@ macro_rules! init_proxy_build {
 /// This macro generates:
 ///
 /// - `pub const GITINFO: Option<GitInfo>` - The version information constant
+/// - `pub const VERSION: &str` - The [`Display`](core::fmt::Display) format of `GITINFO`, usable in `const` contexts
+/// - `pub const LONG_VERSION: &str` - `VERSION` followed by one line per field of `GITINFO`
+/// - `pub const VERSION_STRING: &str` - The version information as a string, see `ProxyBuildConfig::version_string_template`
+/// - `pub const SUBMODULES: &[SubmoduleInfo]` - The version information of the submodules, see `ProxyBuildConfig::submodules`
+/// - One `pub const {NAME}: Option<GitInfo>` for each repository added with `ProxyBuildConfig::additional_repository`
 /// - Re-exports all public items from `git2version` (via `pub use git2version::*`)
 ///
+/// # Named Proxies
+///
+/// For proxies that were given a name in `init_proxy_build!`, pass the name of the constant to generate and the proxy name:
+///
+/// ```ignore
+/// git2version::init_proxy_lib!();
+/// git2version::init_proxy_lib!(SERVER_GITINFO, "server");
+/// ```
+///
+/// This only generates `pub const SERVER_GITINFO: Option<GitInfo>`. `VERSION`, `LONG_VERSION`, `VERSION_STRING`, `SUBMODULES`,
+/// the constants for additional repositories, the link section and the re-exports are only generated by `init_proxy_lib!()`.
+///
 /// # Usage
 ///
 /// In your proxy crate's `src/lib.rs`:
@@ -176,18 +238,20 @@ macro_rules! init_proxy_build {
 ///
 /// The generated constant has type `Option<GitInfo<'static, 'static>>` where:
 ///
-/// - `Some(GitInfo { ... })` - Git information was successfully retrieved at build time
+/// - `Some(GitInfo { ... })` - Git information was successfully retrieved at build time.
+///   Check [`GitInfo::source`](crate::GitInfo::source) to see whether it came from git or from a fallback.
 /// - `None` - Git information could not be retrieved (not in a repo, empty repo, errors, etc.)
 ///
 /// # Display Format
 ///
-/// When converted to a string via [`Display`](std::fmt::Display), `GitInfo` produces
-/// output in the format: `{tag}+{commits}.g{commit_id}[.modified]`
+/// When converted to a string via [`Display`](core::fmt::Display), `GitInfo` produces
+/// output in the format: `{tag}+{commits}.g{commit_id}[.modified][.{source}]`
 ///
 /// Examples:
 /// - `v1.2.3+0.gabcdef1234` - On tag v1.2.3, clean working directory
 /// - `v1.2.3+5.gabcdef1234.modified` - 5 commits after v1.2.3, uncommitted changes
 /// - `unknown.gabcdef1234` - No ancestor tag found
+/// - `v1.2.3+0.gabcdef1234.environment` - Set through `GIT2VERSION_*` environment variables
 ///
 /// # Requirements
 ///
@@ -211,6 +275,8 @@ macro_rules! init_proxy_lib {
         /// - The closest ancestor tag (if any) and number of commits since that tag
         /// - The shortened commit ID (10 characters)
         /// - Whether the working directory had uncommitted changes
+        /// - Where the version information came from (git, environment variables, a snapshot file or a `Cargo.toml`)
+        /// - The name of the linked worktree, if it was built in one (see `git worktree`)
         ///
         /// # Value
         ///
@@ -220,12 +286,13 @@ macro_rules! init_proxy_lib {
         ///
         /// # Display Format
         ///
-        /// When converted to a string, the format is: `{tag}+{commits}.g{commit_id}[.modified]`
+        /// When converted to a string, the format is: `{tag}+{commits}.g{commit_id}[.modified][.{source}]`
         ///
         /// Examples:
         /// - `v1.2.3+0.gabcdef1234` - On tag v1.2.3, clean working directory
         /// - `v1.2.3+5.gabcdef1234.modified` - 5 commits after tag, with local changes
         /// - `unknown.gabcdef1234` - No ancestor tag found
+        /// - `v1.2.3+0.gabcdef1234.environment` - Set through `GIT2VERSION_*` environment variables
         ///
         /// # Example
         ///
@@ -238,32 +305,77 @@ macro_rules! init_proxy_lib {
         ///     }
         /// }
         /// ```
-        pub const GITINFO: Option<$crate::GitInfo> = if $crate::konst::result::unwrap!(
-            $crate::konst::primitive::parse_bool(env!("PACKAGEVERSION_GITVERSION_IS_KNOWN"))
-        ) {
-            Some($crate::GitInfo {
-                tag_info: if $crate::konst::result::unwrap!($crate::konst::primitive::parse_bool(
-                    env!("PACKAGEVERSION_GITVERSION_HAS_TAG")
-                )) {
-                    Some($crate::TagInfo {
-                        tag: env!("PACKAGEVERSION_GITVERSION_TAG"),
-                        commits_since_tag: $crate::konst::result::unwrap!({
-                            let mut parser = $crate::konst::parsing::Parser::new(env!(
-                                "PACKAGEVERSION_GITVERSION_COMMITS_SINCE_TAG"
-                            ));
-                            parser.parse_u32()
-                        }),
-                    })
-                } else {
-                    None
-                },
-                commit_id: env!("PACKAGEVERSION_GITVERSION_COMMIT_ID"),
-                modified: $crate::konst::result::unwrap!($crate::konst::primitive::parse_bool(
-                    env!("PACKAGEVERSION_GITVERSION_MODIFIED")
-                )),
-            })
-        } else {
-            None
+        pub const GITINFO: Option<$crate::GitInfo> = __git2version_generated::GITINFO;
+
+        /// The [`Display`](core::fmt::Display) format of [`GITINFO`], or `"unknown"` if `GITINFO` is `None`.
+        ///
+        /// Unlike formatting `GITINFO` at runtime, this can be used in `const` contexts, e.g. with clap:
+        ///
+        /// ```ignore
+        /// #[derive(clap::Parser)]
+        /// #[command(version = version_proxy::VERSION, long_version = version_proxy::LONG_VERSION)]
+        /// struct Args {}
+        /// ```
+        pub const VERSION: &str = __git2version_generated::VERSION;
+
+        /// [`VERSION`] followed by one `name: value` line for each field of [`GITINFO`], or just `"unknown"`
+        /// if `GITINFO` is `None`.
+        ///
+        /// Example:
+        ///
+        /// ```text
+        /// v1.2.3+5.gabcdef1234.modified
+        /// tag: v1.2.3
+        /// commits since tag: 5
+        /// commit: abcdef1234
+        /// modified: true
+        /// source: git
+        /// ```
+        pub const LONG_VERSION: &str = __git2version_generated::LONG_VERSION;
+
+        /// Version string generated at build time.
+        ///
+        /// By default, this is the same as [`VERSION`]. A custom template can be configured in `build.rs` with
+        /// `ProxyBuildConfig::version_string_template`.
+        pub const VERSION_STRING: &str = __git2version_generated::VERSION_STRING;
+
+        /// Version information of the git submodules of the repository, sorted by path.
+        ///
+        /// This is only filled if `ProxyBuildConfig::submodules` is enabled in `build.rs`, otherwise it is empty.
+        pub const SUBMODULES: &[$crate::SubmoduleInfo] = __git2version_generated::SUBMODULES;
+
+        // Constants for `ProxyBuildConfig::additional_repository`. The module is empty if there aren't any.
+        #[allow(unused_imports)]
+        pub use __git2version_generated::additional_repositories::*;
+
+        // The build script generates the values of the constants above (and the link section static
+        // if `ProxyBuildConfig::embed_link_section` is enabled) as Rust code.
+        #[doc(hidden)]
+        mod __git2version_generated {
+            #[allow(unused_imports)]
+            use $crate::{GitInfo, SubmoduleInfo, TagInfo, VersionSource};
+
+            include!(concat!(env!("OUT_DIR"), "/git2version_gitinfo.rs"));
+        }
+    };
+    ($const_name:ident, $name:literal) => {
+        #[doc = concat!("Git version information of the `", $name, "` proxy, extracted at build time.")]
+        ///
+        /// See `GITINFO` in the `init_proxy_lib!` documentation for details.
+        pub const $const_name: Option<$crate::GitInfo> = {
+            // The module is inside of this block so that several named proxies don't conflict
+            #[allow(dead_code, unused_imports)]
+            mod generated {
+                use $crate::{GitInfo, SubmoduleInfo, TagInfo, VersionSource};
+
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/git2version_gitinfo_",
+                    $name,
+                    ".rs"
+                ));
+            }
+            generated::GITINFO
         };
     };
 }
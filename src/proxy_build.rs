@@ -0,0 +1,1059 @@
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use super::cargo_manifest;
+use super::env_override::{self, EnvOverrides};
+use super::get_git_info::{get_git_info, get_submodule_info};
+use super::git_helpers;
+use super::gitinfo::VersionSource;
+use super::gitinfo_owned::{GitInfoOwned, SubmoduleInfoOwned};
+use super::link_section;
+use super::proxy_build_config::{
+    AdditionalRepository, CargoVersionCheck, OnVersionMismatch, ProxyBuildConfig,
+    RepositoryLocation,
+};
+use super::snapshot;
+use super::template::{FormatWith, TemplateValues};
+
+/// Name of the file in `OUT_DIR` with the generated code that `init_proxy_lib!` includes for the proxy `proxy_name`.
+/// This needs to be kept in sync with the file names in `init_proxy_lib!`.
+fn generated_file_name(proxy_name: Option<&str>) -> String {
+    match proxy_name {
+        None => "git2version_gitinfo.rs".to_string(),
+        Some(proxy_name) => format!("git2version_gitinfo_{proxy_name}.rs"),
+    }
+}
+
+/// Implementation of the [`init_proxy_build!`](crate::init_proxy_build) macro.
+///
+/// This is only public because the macro needs to call it. Use the macro instead of calling this directly.
+#[doc(hidden)]
+pub fn run_proxy_build(
+    cargo_manifest_dir: &str,
+    proxy_name: Option<&str>,
+    config: &ProxyBuildConfig,
+) {
+    // Environment variables can override (parts of) the version information or substitute for it if there is no git repository
+    for env_var in env_override::ALL_ENV_VARS {
+        println!(
+            "cargo:rerun-if-env-changed={}",
+            env_override::env_var_name(env_var, proxy_name)
+        );
+    }
+
+    // Packaging setups often point git to the repository through environment variables
+    if config.repository_dir.is_none() {
+        for env_var in git_helpers::GIT_ENV_VARS {
+            println!("cargo:rerun-if-env-changed={env_var}");
+        }
+    }
+    for additional_repository in &config.additional_repositories {
+        if let RepositoryLocation::EnvVar(env_var) = &additional_repository.location {
+            println!("cargo:rerun-if-env-changed={env_var}");
+        }
+    }
+
+    let output = compute_proxy_build(cargo_manifest_dir, proxy_name, config);
+    for warning in &output.warnings {
+        println!("cargo:warning={warning}");
+    }
+    for path in &output.rerun_if_changed {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    let result = output.result.unwrap_or_else(|err| panic!("{err}"));
+    write_out_dir_file(
+        &generated_file_name(proxy_name),
+        generated_code(&result, config.embed_link_section).as_bytes(),
+    );
+}
+
+/// The version information that [`init_proxy_build!`](crate::init_proxy_build) passes on to `init_proxy_lib!`.
+///
+/// This is only public because the `git2version` command line tool needs it.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ProxyBuildResult {
+    /// Value of the `GITINFO` constant
+    pub repository_version: Option<GitInfoOwned>,
+
+    /// Value of the `VERSION` constant
+    pub version: String,
+
+    /// Value of the `LONG_VERSION` constant
+    pub long_version: String,
+
+    /// Value of the `VERSION_STRING` constant
+    pub version_string: String,
+
+    /// Value of the `SUBMODULES` constant
+    pub submodules: Vec<SubmoduleInfoOwned>,
+
+    /// Names and values of the constants for `ProxyBuildConfig::additional_repository`
+    pub additional_repositories: Vec<(String, Option<GitInfoOwned>)>,
+}
+
+/// A [`ProxyBuildResult`] or an error that fails the build, together with the warnings and the paths
+/// that should rerun the build script when they change.
+///
+/// This is only public because the `git2version` command line tool needs it.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ProxyBuildOutput {
+    /// The computed version information, or the reason why the build should fail
+    pub result: Result<ProxyBuildResult, String>,
+
+    /// Warnings that should be shown to the user
+    pub warnings: Vec<String>,
+
+    rerun_if_changed: Vec<PathBuf>,
+}
+
+/// Computes the version information exactly like [`init_proxy_build!`](crate::init_proxy_build) would, but without
+/// talking to cargo.
+///
+/// `proxy_name` is the name passed to `init_proxy_build!`, or `None` for the unnamed proxy.
+///
+/// This is only public because the `git2version` command line tool needs it.
+#[doc(hidden)]
+pub fn compute_proxy_build(
+    cargo_manifest_dir: &str,
+    proxy_name: Option<&str>,
+    config: &ProxyBuildConfig,
+) -> ProxyBuildOutput {
+    let mut warnings = Vec::new();
+    let mut rerun_if_changed = Vec::new();
+    let result = compute_proxy_build_impl(
+        cargo_manifest_dir,
+        proxy_name,
+        config,
+        &mut warnings,
+        &mut rerun_if_changed,
+    );
+    ProxyBuildOutput {
+        result,
+        warnings,
+        rerun_if_changed,
+    }
+}
+
+fn compute_proxy_build_impl(
+    cargo_manifest_dir: &str,
+    proxy_name: Option<&str>,
+    config: &ProxyBuildConfig,
+    warnings: &mut Vec<String>,
+    rerun_if_changed: &mut Vec<PathBuf>,
+) -> Result<ProxyBuildResult, String> {
+    if let Some(proxy_name) = proxy_name {
+        check_proxy_name(proxy_name)?;
+        check_named_proxy_config(proxy_name, config)?;
+    }
+    check_additional_repository_names(&config.additional_repositories)?;
+    let repository_dir = match &config.repository_dir {
+        Some(repository_dir) => Path::new(cargo_manifest_dir).join(repository_dir),
+        None => PathBuf::from(cargo_manifest_dir),
+    };
+
+    let repo = discover_repository(
+        cargo_manifest_dir,
+        &repository_dir,
+        config,
+        warnings,
+        rerun_if_changed,
+    );
+    add_rerun_paths(repo.as_ref(), &repository_dir, rerun_if_changed);
+
+    let repository_version = match &repo {
+        Some(repo) => match get_git_info(repo) {
+            Ok(git_info) => Some(git_info),
+            Err(err) => {
+                warnings.push(format!("Error getting version info from git: {err}"));
+                None
+            }
+        },
+        // Without a git repository, e.g. in a source tarball created by `cargo package`, fall back to the snapshot file if there is one
+        None => match snapshot::read_snapshot(&repository_dir) {
+            Ok(Some(git_info)) => {
+                warnings.push(format!(
+                    "Using version info from {}",
+                    snapshot::SNAPSHOT_FILE_NAME
+                ));
+                Some(GitInfoOwned {
+                    source: VersionSource::SnapshotFile,
+                    worktree: None,
+                    ..git_info
+                })
+            }
+            Ok(None) => None,
+            Err(err) => {
+                warnings.push(format!(
+                    "Error reading version info from {}: {err}",
+                    snapshot::SNAPSHOT_FILE_NAME
+                ));
+                None
+            }
+        },
+    };
+
+    // Environment variables can override (parts of) the version information or substitute for it if there is no git repository
+    let repository_version = EnvOverrides::from_env(proxy_name)
+        .and_then(|overrides| overrides.apply(repository_version))
+        .map_err(|err| format!("Error applying version overrides from environment: {err}"))?;
+
+    let repository_version = match (repository_version, &config.cargo_manifest_fallback) {
+        (None, Some(crate_dir)) => {
+            let crate_dir = Path::new(cargo_manifest_dir).join(crate_dir);
+            match cargo_manifest::read_cargo_manifest_version(&crate_dir) {
+                Ok(version) => {
+                    rerun_if_changed.extend(version.manifest_paths.iter().cloned());
+                    warnings.push(format!(
+                        "Using version {} from {} instead",
+                        version.version,
+                        crate_dir.join("Cargo.toml").display()
+                    ));
+                    Some(version.into_git_info())
+                }
+                Err(err) => {
+                    warnings.push(format!("Error getting version info from Cargo.toml: {err}"));
+                    None
+                }
+            }
+        }
+        (repository_version, _) => repository_version,
+    };
+
+    if let (Some(repository_version), Some(check)) =
+        (&repository_version, &config.cargo_version_check)
+    {
+        check_cargo_version(
+            cargo_manifest_dir,
+            repository_version,
+            check,
+            config
+                .cargo_version_check_tag_prefix
+                .as_deref()
+                .unwrap_or("v"),
+            warnings,
+            rerun_if_changed,
+        )?;
+    }
+
+    let submodules = match &repo {
+        Some(repo) if config.submodules => get_submodule_info(repo).unwrap_or_else(|err| {
+            warnings.push(format!("Error getting version info of submodules: {err}"));
+            Vec::new()
+        }),
+        _ => Vec::new(),
+    };
+
+    let additional_repositories = config
+        .additional_repositories
+        .iter()
+        .map(|additional_repository| {
+            let git_info = additional_repository_info(
+                cargo_manifest_dir,
+                additional_repository,
+                warnings,
+                rerun_if_changed,
+            );
+            (additional_repository.const_name.clone(), git_info)
+        })
+        .collect();
+
+    let version = repository_version
+        .as_ref()
+        .map(|repository_version| repository_version.as_borrowed().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let long_version = long_version(&version, repository_version.as_ref());
+    let version_string = version_string(config, repo.as_ref(), repository_version.as_ref())?;
+    Ok(ProxyBuildResult {
+        repository_version,
+        version,
+        long_version,
+        version_string,
+        submodules,
+        additional_repositories,
+    })
+}
+
+/// Finds the git repository containing `repository_dir`, unless it's excluded by `ProxyBuildConfig::ceiling_dir`
+/// or `ProxyBuildConfig::require_tracked`.
+fn discover_repository(
+    cargo_manifest_dir: &str,
+    repository_dir: &Path,
+    config: &ProxyBuildConfig,
+    warnings: &mut Vec<String>,
+    rerun_if_changed: &mut Vec<PathBuf>,
+) -> Option<Repository> {
+    // `GIT_DIR` and friends refer to the repository of the current directory, i.e. the proxy crate's
+    let repo = match &config.repository_dir {
+        Some(_) => Repository::discover(repository_dir),
+        None => git_helpers::open_repository_from_env(repository_dir),
+    };
+    let repo = match repo {
+        Ok(repo) => repo,
+        Err(err) => {
+            warnings.push(format!(
+                "Error getting version info from git, {}",
+                open_error_message(&err, repository_dir)
+            ));
+            return None;
+        }
+    };
+
+    // If `GIT_DIR` is set, the repository was explicitly selected instead of discovered
+    if config.repository_dir.is_none() && std::env::var_os("GIT_DIR").is_some() {
+        return Some(repo);
+    }
+    let mut ceiling_dirs: Vec<PathBuf> = config
+        .ceiling_dirs
+        .iter()
+        .map(|ceiling_dir| Path::new(cargo_manifest_dir).join(ceiling_dir))
+        .collect();
+    if config.repository_dir.is_none() {
+        ceiling_dirs.extend(git_helpers::ceiling_dirs_from_env());
+    }
+    if let Some(ceiling_dir) =
+        git_helpers::crossed_ceiling_dir(&repo, repository_dir, &ceiling_dirs)
+    {
+        warnings.push(format!(
+            "Ignoring git repository at {} because it is outside of the ceiling directory {}",
+            repository_root(&repo).display(),
+            ceiling_dir.display()
+        ));
+        return None;
+    }
+    if config.require_tracked {
+        match git_helpers::tracks_files_in(&repo, repository_dir) {
+            Ok(true) => {}
+            Ok(false) => {
+                warnings.push(format!(
+                    "Ignoring git repository at {} because it doesn't track any files in {}",
+                    repository_root(&repo).display(),
+                    repository_dir.display()
+                ));
+                // Rerun once the files get added to the repository
+                rerun_if_changed.push(repo.path().join("index"));
+                return None;
+            }
+            Err(err) => {
+                warnings.push(format!(
+                    "Error getting version info from git, couldn't read the index of the git repository at {}: {err}",
+                    repository_root(&repo).display()
+                ));
+                return None;
+            }
+        }
+    }
+    Some(repo)
+}
+
+fn repository_root(repo: &Repository) -> &Path {
+    repo.workdir().unwrap_or_else(|| repo.path())
+}
+
+/// Describes why opening the git repository containing `repository_dir` failed.
+///
+/// Since CVE-2022-24765, git refuses to open repositories owned by a different user unless they're listed in git's
+/// `safe.directory` setting. This often happens in containers and CI jobs, so we tell the user how to fix it.
+fn open_error_message(err: &git2::Error, repository_dir: &Path) -> String {
+    if err.code() == git2::ErrorCode::Owner {
+        format!(
+            "the git repository containing {} is owned by a different user: {err}. \
+            If you trust it, add it to git's safe.directory setting, e.g. with `git config --global --add safe.directory <repository>`",
+            repository_dir.display()
+        )
+    } else {
+        format!(
+            "didn't find git repository in {}: {err}",
+            repository_dir.display()
+        )
+    }
+}
+
+fn add_rerun_paths(
+    repo: Option<&Repository>,
+    repository_dir: &Path,
+    rerun_if_changed: &mut Vec<PathBuf>,
+) {
+    if let Some(repo) = repo {
+        // Rerun the build script if any files changed. This is necessary to correctly update
+        // the `.modified` flag of version numbers. Bare repositories don't have a working directory.
+        if let Some(workdir) = repo.workdir() {
+            rerun_if_changed.push(workdir.to_path_buf());
+        }
+
+        // Also rerun the build script if anything in the .git repository changed.
+        // This is for the case where our `Cargo.toml` is in a subdirectory of the
+        // main git repository. In this case, we still need to react to changes in
+        // the git repository.
+        rerun_if_changed.push(repo.path().to_path_buf());
+
+        // In a linked worktree, `repo.path()` is `.git/worktrees/<name>` and only contains the worktree's
+        // HEAD and index. Refs (e.g. new tags) live in the common directory shared by all worktrees.
+        if repo.is_worktree() {
+            let common_dir = repo.commondir();
+            rerun_if_changed.push(common_dir.join("refs"));
+            // Cargo reruns the build script every time if a path doesn't exist, so only watch `packed-refs` if it's there.
+            // Creating it usually also removes loose refs from the `refs` directory, which triggers a rerun.
+            let packed_refs = common_dir.join("packed-refs");
+            if packed_refs.exists() {
+                rerun_if_changed.push(packed_refs);
+            }
+        }
+    } else {
+        // We didn't find a git repository. Let's rerun if the directory of the `Cargo.toml`
+        // (or the configured repository directory) changed to check if a git repository got added.
+        // Note: This won't catch cases where a git repository is added as a parent directory,
+        // but probably nothing we can do about that.
+        // This also catches the snapshot file being added, changed or removed.
+        rerun_if_changed.push(repository_dir.to_path_buf());
+    }
+}
+
+/// Looks up the version information for `ProxyBuildConfig::additional_repository`.
+fn additional_repository_info(
+    cargo_manifest_dir: &str,
+    additional_repository: &AdditionalRepository,
+    warnings: &mut Vec<String>,
+    rerun_if_changed: &mut Vec<PathBuf>,
+) -> Option<GitInfoOwned> {
+    let const_name = &additional_repository.const_name;
+    let repository_dir = match &additional_repository.location {
+        RepositoryLocation::Path(path) => Path::new(cargo_manifest_dir).join(path),
+        RepositoryLocation::EnvVar(env_var) => match std::env::var_os(env_var) {
+            Some(path) => Path::new(cargo_manifest_dir).join(path),
+            None => {
+                warnings.push(format!(
+                    "Error getting version info for {const_name}: {env_var} isn't set"
+                ));
+                return None;
+            }
+        },
+    };
+    let repo = match Repository::discover(&repository_dir) {
+        Ok(repo) => Some(repo),
+        Err(err) => {
+            warnings.push(format!(
+                "Error getting version info for {const_name}, {}",
+                open_error_message(&err, &repository_dir)
+            ));
+            None
+        }
+    };
+    add_rerun_paths(repo.as_ref(), &repository_dir, rerun_if_changed);
+    match get_git_info(&repo?) {
+        Ok(git_info) => Some(git_info),
+        Err(err) => {
+            warnings.push(format!(
+                "Error getting version info for {const_name} from git: {err}"
+            ));
+            None
+        }
+    }
+}
+
+/// The constants for additional repositories are generated into the proxy crate next to the other constants,
+/// so their names must be valid identifiers that don't conflict with anything else.
+fn check_additional_repository_names(
+    additional_repositories: &[AdditionalRepository],
+) -> Result<(), String> {
+    const RESERVED_NAMES: [&str; 5] = [
+        "GITINFO",
+        "VERSION",
+        "LONG_VERSION",
+        "VERSION_STRING",
+        "SUBMODULES",
+    ];
+    for (index, additional_repository) in additional_repositories.iter().enumerate() {
+        let const_name = additional_repository.const_name.as_str();
+        let is_identifier = const_name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && const_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && const_name != "_";
+        if !is_identifier {
+            return Err(format!(
+                "Invalid constant name '{const_name}' for additional repository: expected an identifier like SIBLING_GITINFO"
+            ));
+        }
+        if RESERVED_NAMES.contains(&const_name)
+            || additional_repositories[..index]
+                .iter()
+                .any(|other| other.const_name == const_name)
+        {
+            return Err(format!(
+                "Constant name '{const_name}' for additional repository is already used"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Proxy names end up in file names and environment variable names, so we only allow a safe set of characters.
+/// Environment variable names use the uppercased name, so we don't allow uppercase letters to keep two proxies
+/// from reading each other's overrides.
+fn check_proxy_name(proxy_name: &str) -> Result<(), String> {
+    if proxy_name.is_empty()
+        || !proxy_name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(format!(
+            "Invalid proxy name '{proxy_name}': only lowercase ASCII letters, digits and '_' are allowed"
+        ));
+    }
+    Ok(())
+}
+
+/// `init_proxy_lib!` only generates the `GITINFO` constant for named proxies, so settings that only affect
+/// the other constants would be silently ignored.
+fn check_named_proxy_config(proxy_name: &str, config: &ProxyBuildConfig) -> Result<(), String> {
+    let unsupported = if config.submodules {
+        "ProxyBuildConfig::submodules"
+    } else if !config.additional_repositories.is_empty() {
+        "ProxyBuildConfig::additional_repository"
+    } else if config.version_string_template.is_some() {
+        "ProxyBuildConfig::version_string_template"
+    } else if config.embed_link_section {
+        "ProxyBuildConfig::embed_link_section"
+    } else {
+        return Ok(());
+    };
+    Err(format!(
+        "{unsupported} isn't supported for the named proxy '{proxy_name}', because init_proxy_lib! only generates a GitInfo constant for named proxies. Use it in the unnamed proxy instead."
+    ))
+}
+
+/// Computes the value of the `VERSION_STRING` constant generated by `init_proxy_lib!`.
+fn version_string(
+    config: &ProxyBuildConfig,
+    repo: Option<&Repository>,
+    repository_version: Option<&GitInfoOwned>,
+) -> Result<String, String> {
+    let template = config.version_string_template.as_deref();
+    let Some(repository_version) = repository_version else {
+        // Still validate the template so that errors don't only show up once the version is known
+        if let Some(template) = template {
+            FormatWith::new(TemplateValues::default(), template).map_err(|err| err.to_string())?;
+        }
+        return Ok("unknown".to_string());
+    };
+    let git_info = repository_version.as_borrowed();
+    let Some(template) = template else {
+        return Ok(git_info.to_string());
+    };
+
+    // The full commit id and branch aren't part of `GitInfo`, so we look them up here. This is only
+    // correct if the version information actually came from the repository and not from somewhere else.
+    let head = repo
+        .filter(|_| repository_version.source == VersionSource::Git)
+        .and_then(|repo| repo.head().ok());
+    let full_commit_id = head
+        .as_ref()
+        .and_then(|head| head.peel_to_commit().ok())
+        .map(|commit| commit.id().to_string());
+    let branch = head
+        .as_ref()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand());
+    let values = TemplateValues {
+        full: full_commit_id.as_deref(),
+        branch,
+        ..TemplateValues::from_git_info(&git_info)
+    };
+    Ok(FormatWith::new(values, template)
+        .map_err(|err| err.to_string())?
+        .to_string())
+}
+
+/// Generates the code that `init_proxy_lib!` includes. It is included into a module that imports `GitInfo`, `TagInfo`,
+/// `VersionSource` and `SubmoduleInfo`.
+///
+/// All strings are written as Rust string literals, so they can contain arbitrary characters.
+fn generated_code(result: &ProxyBuildResult, embed_link_section: bool) -> String {
+    let gitinfo = gitinfo_code(result.repository_version.as_ref());
+    let submodules: String = result
+        .submodules
+        .iter()
+        .map(|submodule| {
+            format!(
+                "\n    SubmoduleInfo {{ name: {:?}, path: {:?}, git_info: {} }},",
+                submodule.name,
+                submodule.path,
+                gitinfo_code(submodule.git_info.as_ref())
+            )
+        })
+        .collect();
+    let submodules = if submodules.is_empty() {
+        submodules
+    } else {
+        submodules + "\n"
+    };
+    let additional_repositories: String = result
+        .additional_repositories
+        .iter()
+        .map(|(const_name, git_info)| {
+            format!(
+                "
+    /// Git version information of an additional repository, see `ProxyBuildConfig::additional_repository`.
+    pub const {const_name}: Option<GitInfo<'static, 'static>> = {};
+",
+                gitinfo_code(git_info.as_ref())
+            )
+        })
+        .collect();
+    let mut code = format!(
+        "// Generated by git2version's init_proxy_build!(), don't edit.
+pub(super) const GITINFO: Option<GitInfo<'static, 'static>> = {gitinfo};
+pub(super) const VERSION: &str = {:?};
+pub(super) const LONG_VERSION: &str = {:?};
+pub(super) const VERSION_STRING: &str = {:?};
+pub(super) const SUBMODULES: &[SubmoduleInfo<'static>] = &[{submodules}];
+
+pub(super) mod additional_repositories {{
+    #[allow(unused_imports)]
+    use super::{{GitInfo, TagInfo, VersionSource}};
+{additional_repositories}}}
+",
+        result.version, result.long_version, result.version_string
+    );
+    if embed_link_section {
+        let blob = link_section::encode(result.repository_version.as_ref());
+        code += &format!(
+            "
+// Embedded into the binary because `ProxyBuildConfig::embed_link_section` is enabled, see `read_link_section`.
+#[used]
+#[cfg_attr(target_vendor = \"apple\", unsafe(link_section = \"__DATA,__git2version\"))]
+#[cfg_attr(not(target_vendor = \"apple\"), unsafe(link_section = \".git2version\"))]
+static GIT2VERSION_LINK_SECTION: [u8; {}] = *b\"{}\";
+",
+            blob.len(),
+            blob.escape_ascii()
+        );
+    }
+    code
+}
+
+fn gitinfo_code(git_info: Option<&GitInfoOwned>) -> String {
+    let Some(git_info) = git_info else {
+        return "None".to_string();
+    };
+    let tag_info = match &git_info.tag_info {
+        Some(tag_info) => format!(
+            "Some(TagInfo {{ tag: {:?}, commits_since_tag: {} }})",
+            tag_info.tag, tag_info.commits_since_tag
+        ),
+        None => "None".to_string(),
+    };
+    format!(
+        "Some(GitInfo {{ tag_info: {tag_info}, commit_id: {:?}, modified: {}, source: VersionSource::{:?}, worktree: {:?} }})",
+        git_info.commit_id, git_info.modified, git_info.source, git_info.worktree
+    )
+}
+
+fn write_out_dir_file(file_name: &str, content: &[u8]) {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .expect("OUT_DIR not set, init_proxy_build! must be called from a build script");
+    let path = Path::new(&out_dir).join(file_name);
+    std::fs::write(&path, content)
+        .unwrap_or_else(|err| panic!("Error writing {}: {err}", path.display()));
+}
+
+fn long_version(version: &str, repository_version: Option<&GitInfoOwned>) -> String {
+    let Some(repository_version) = repository_version else {
+        return version.to_string();
+    };
+    let mut long_version = version.to_string();
+    match &repository_version.tag_info {
+        Some(tag_info) => {
+            long_version += &format!("\ntag: {}", tag_info.tag);
+            long_version += &format!("\ncommits since tag: {}", tag_info.commits_since_tag);
+        }
+        None => long_version += "\ntag: none",
+    }
+    if !repository_version.commit_id.is_empty() {
+        long_version += &format!("\ncommit: {}", repository_version.commit_id);
+    }
+    long_version += &format!("\nmodified: {}", repository_version.modified);
+    long_version += &format!("\nsource: {}", repository_version.source.name());
+    if let Some(worktree) = &repository_version.worktree {
+        long_version += &format!("\nworktree: {worktree}");
+    }
+    long_version
+}
+
+fn check_cargo_version(
+    cargo_manifest_dir: &str,
+    repository_version: &GitInfoOwned,
+    check: &CargoVersionCheck,
+    tag_prefix: &str,
+    warnings: &mut Vec<String>,
+    rerun_if_changed: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    if repository_version.source == VersionSource::CargoManifest {
+        // The tag already is the Cargo.toml version, no need to compare
+        return Ok(());
+    }
+    let Some(tag_info) = &repository_version.tag_info else {
+        return Ok(());
+    };
+    if tag_info.commits_since_tag != 0 {
+        // We only check versions if HEAD is exactly on a tag
+        return Ok(());
+    }
+    let crate_dir = Path::new(cargo_manifest_dir).join(&check.crate_dir);
+    // Not being able to read the version is a configuration error, not a mismatch, so `on_mismatch` doesn't apply to it
+    let version = cargo_manifest::read_cargo_manifest_version(&crate_dir).map_err(|err| {
+        format!("Error getting version info from Cargo.toml for the version check: {err}")
+    })?;
+    rerun_if_changed.extend(version.manifest_paths.iter().cloned());
+    let result =
+        cargo_manifest::check_tag_matches_version(&tag_info.tag, tag_prefix, &version.version);
+    match (result, check.on_mismatch) {
+        (Ok(()), _) => Ok(()),
+        (Err(err), OnVersionMismatch::Warn) => {
+            warnings.push(err.to_string());
+            Ok(())
+        }
+        (Err(err), OnVersionMismatch::Fail) => Err(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitinfo_owned::TagInfoOwned;
+
+    #[test]
+    fn long_version_unknown() {
+        assert_eq!("unknown", long_version("unknown", None));
+    }
+
+    #[test]
+    fn long_version_with_tag() {
+        let repository_version = GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        assert_eq!(
+            "v1.2.3+5.gabcdef1234.modified\ntag: v1.2.3\ncommits since tag: 5\ncommit: abcdef1234\nmodified: true\nsource: git",
+            long_version(
+                &repository_version.as_borrowed().to_string(),
+                Some(&repository_version)
+            )
+        );
+    }
+
+    #[test]
+    fn long_version_without_tag() {
+        let repository_version = GitInfoOwned {
+            tag_info: None,
+            commit_id: "abcdef1234".to_string(),
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        };
+        assert_eq!(
+            "unknown.gabcdef1234.environment\ntag: none\ncommit: abcdef1234\nmodified: false\nsource: environment",
+            long_version(
+                &repository_version.as_borrowed().to_string(),
+                Some(&repository_version)
+            )
+        );
+    }
+
+    #[test]
+    fn long_version_without_commit() {
+        let repository_version = GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "1.2.3".to_string(),
+                commits_since_tag: 0,
+            }),
+            commit_id: String::new(),
+            modified: false,
+            source: VersionSource::CargoManifest,
+            worktree: None,
+        };
+        assert_eq!(
+            "1.2.3+0.cargo-manifest\ntag: 1.2.3\ncommits since tag: 0\nmodified: false\nsource: cargo-manifest",
+            long_version(
+                &repository_version.as_borrowed().to_string(),
+                Some(&repository_version)
+            )
+        );
+    }
+
+    fn result(repository_version: Option<GitInfoOwned>) -> ProxyBuildResult {
+        let version = repository_version
+            .as_ref()
+            .map(|repository_version| repository_version.as_borrowed().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        ProxyBuildResult {
+            long_version: long_version(&version, repository_version.as_ref()),
+            version_string: version.clone(),
+            version,
+            repository_version,
+            submodules: Vec::new(),
+            additional_repositories: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn generated_code_unknown() {
+        assert_eq!(
+            "// Generated by git2version's init_proxy_build!(), don't edit.
+pub(super) const GITINFO: Option<GitInfo<'static, 'static>> = None;
+pub(super) const VERSION: &str = \"unknown\";
+pub(super) const LONG_VERSION: &str = \"unknown\";
+pub(super) const VERSION_STRING: &str = \"unknown\";
+pub(super) const SUBMODULES: &[SubmoduleInfo<'static>] = &[];
+
+pub(super) mod additional_repositories {
+    #[allow(unused_imports)]
+    use super::{GitInfo, TagInfo, VersionSource};
+}
+",
+            generated_code(&result(None), false)
+        );
+    }
+
+    #[test]
+    fn generated_code_with_submodules() {
+        let code = generated_code(
+            &ProxyBuildResult {
+                submodules: vec![
+                    SubmoduleInfoOwned {
+                        name: "zlib".to_string(),
+                        path: "vendor/zlib".to_string(),
+                        git_info: Some(GitInfoOwned {
+                            tag_info: None,
+                            commit_id: "abcdef1234".to_string(),
+                            modified: true,
+                            source: VersionSource::Git,
+                            worktree: None,
+                        }),
+                    },
+                    SubmoduleInfoOwned {
+                        name: "uninitialized".to_string(),
+                        path: "vendor/uninitialized".to_string(),
+                        git_info: None,
+                    },
+                ],
+                ..result(None)
+            },
+            false,
+        );
+        assert!(
+            code.contains(
+                r#"pub(super) const SUBMODULES: &[SubmoduleInfo<'static>] = &[
+    SubmoduleInfo { name: "zlib", path: "vendor/zlib", git_info: Some(GitInfo { tag_info: None, commit_id: "abcdef1234", modified: true, source: VersionSource::Git, worktree: None }) },
+    SubmoduleInfo { name: "uninitialized", path: "vendor/uninitialized", git_info: None },
+];"#
+            ),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn generated_code_escapes_strings() {
+        let code = generated_code(
+            &result(Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "v1.2.3 \"quoted\"\nünïcödé".to_string(),
+                    commits_since_tag: 5,
+                }),
+                commit_id: "abcdef1234".to_string(),
+                modified: true,
+                source: VersionSource::Environment,
+                worktree: Some("my-worktree".to_string()),
+            })),
+            false,
+        );
+        assert!(
+            code.contains(
+                r#"pub(super) const GITINFO: Option<GitInfo<'static, 'static>> = Some(GitInfo { tag_info: Some(TagInfo { tag: "v1.2.3 \"quoted\"\nünïcödé", commits_since_tag: 5 }), commit_id: "abcdef1234", modified: true, source: VersionSource::Environment, worktree: Some("my-worktree") });"#
+            ),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                r#"pub(super) const LONG_VERSION: &str = "v1.2.3 \"quoted\"\nünïcödé+5.gabcdef1234.modified.environment\ntag: v1.2.3 \"quoted\"\nünïcödé\ncommits since tag: 5\ncommit: abcdef1234\nmodified: true\nsource: environment\nworktree: my-worktree";"#
+            ),
+            "{code}"
+        );
+        assert!(!code.contains("link_section"), "{code}");
+    }
+
+    #[test]
+    fn proxy_names() {
+        assert_eq!("git2version_gitinfo.rs", generated_file_name(None));
+        assert_eq!(
+            "git2version_gitinfo_server.rs",
+            generated_file_name(Some("server"))
+        );
+        assert!(check_proxy_name("server").is_ok());
+        assert!(check_proxy_name("my_server_2").is_ok());
+        assert!(check_proxy_name("").is_err());
+        // These would collide with `my_server` in environment variable names
+        assert!(check_proxy_name("my-server").is_err());
+        assert!(check_proxy_name("My_Server").is_err());
+        assert!(check_proxy_name("../server").is_err());
+        assert!(check_proxy_name("server name").is_err());
+    }
+
+    #[test]
+    fn generated_code_with_additional_repositories() {
+        let code = generated_code(
+            &ProxyBuildResult {
+                additional_repositories: vec![
+                    (
+                        "SIBLING_GITINFO".to_string(),
+                        Some(GitInfoOwned {
+                            tag_info: None,
+                            commit_id: "abcdef1234".to_string(),
+                            modified: false,
+                            source: VersionSource::Git,
+                            worktree: None,
+                        }),
+                    ),
+                    ("MISSING_GITINFO".to_string(), None),
+                ],
+                ..result(None)
+            },
+            false,
+        );
+        assert!(
+            code.contains(
+                r#"    pub const SIBLING_GITINFO: Option<GitInfo<'static, 'static>> = Some(GitInfo { tag_info: None, commit_id: "abcdef1234", modified: false, source: VersionSource::Git, worktree: None });"#
+            ),
+            "{code}"
+        );
+        assert!(
+            code.contains(
+                "    pub const MISSING_GITINFO: Option<GitInfo<'static, 'static>> = None;"
+            ),
+            "{code}"
+        );
+    }
+
+    fn additional_repository(const_name: &str) -> AdditionalRepository {
+        AdditionalRepository {
+            const_name: const_name.to_string(),
+            location: RepositoryLocation::Path(PathBuf::from("..")),
+        }
+    }
+
+    #[test]
+    fn additional_repository_names() {
+        assert!(check_additional_repository_names(&[]).is_ok());
+        assert!(
+            check_additional_repository_names(&[
+                additional_repository("SIBLING_GITINFO"),
+                additional_repository("_other2"),
+            ])
+            .is_ok()
+        );
+        for invalid in ["", "_", "2ND", "SIBLING-GITINFO", "SIBLING GITINFO"] {
+            let err =
+                check_additional_repository_names(&[additional_repository(invalid)]).unwrap_err();
+            assert!(err.contains("Invalid constant name"), "{err}");
+        }
+        for conflicting in [
+            vec![additional_repository("VERSION")],
+            vec![
+                additional_repository("SIBLING_GITINFO"),
+                additional_repository("SIBLING_GITINFO"),
+            ],
+        ] {
+            let err = check_additional_repository_names(&conflicting).unwrap_err();
+            assert!(err.contains("already used"), "{err}");
+        }
+    }
+
+    #[test]
+    fn generated_code_with_link_section() {
+        let code = generated_code(&result(None), true);
+        let blob = link_section::encode(None);
+        assert!(
+            code.contains(&format!(
+                "static GIT2VERSION_LINK_SECTION: [u8; {}] = *b\"{}\";",
+                blob.len(),
+                blob.escape_ascii()
+            )),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn open_error_message_for_ownership_error() {
+        let err = git2::Error::new(
+            git2::ErrorCode::Owner,
+            git2::ErrorClass::Config,
+            "repository path '/repo/' is not owned by current user",
+        );
+        let message = open_error_message(&err, Path::new("/repo/version_proxy"));
+        assert!(
+            message.starts_with("the git repository containing /repo/version_proxy is owned by a different user: repository path '/repo/' is not owned by current user"),
+            "{message}"
+        );
+        assert!(
+            message.contains("git config --global --add safe.directory"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn open_error_message_for_missing_repository() {
+        let err = git2::Error::new(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Repository,
+            "could not find repository",
+        );
+        assert_eq!(
+            format!("didn't find git repository in /crate: {err}"),
+            open_error_message(&err, Path::new("/crate"))
+        );
+    }
+
+    #[test]
+    fn named_proxy_config() {
+        assert!(check_named_proxy_config("server", &ProxyBuildConfig::new()).is_ok());
+        let err = check_named_proxy_config("server", &ProxyBuildConfig::new().submodules(true))
+            .unwrap_err();
+        assert!(err.contains("ProxyBuildConfig::submodules"), "{err}");
+        let err = check_named_proxy_config(
+            "server",
+            &ProxyBuildConfig::new().additional_repository("SIBLING_GITINFO", "../sibling"),
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("ProxyBuildConfig::additional_repository"),
+            "{err}"
+        );
+        let err = check_named_proxy_config(
+            "server",
+            &ProxyBuildConfig::new().version_string_template("{tag}"),
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("ProxyBuildConfig::version_string_template"),
+            "{err}"
+        );
+        let err =
+            check_named_proxy_config("server", &ProxyBuildConfig::new().embed_link_section(true))
+                .unwrap_err();
+        assert!(
+            err.contains("ProxyBuildConfig::embed_link_section"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::path::PathBuf;
+
+/// Configuration for [`init_proxy_build!`](crate::init_proxy_build).
+///
+/// The default configuration is what `init_proxy_build!()` uses when called without arguments.
+///
+/// # Example
+///
+/// ```ignore
+/// fn main() {
+///     git2version::init_proxy_build!(
+///         git2version::ProxyBuildConfig::new().cargo_manifest_fallback("..")
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyBuildConfig {
+    pub(crate) repository_dir: Option<PathBuf>,
+    pub(crate) ceiling_dirs: Vec<PathBuf>,
+    pub(crate) require_tracked: bool,
+    pub(crate) cargo_manifest_fallback: Option<PathBuf>,
+    pub(crate) cargo_version_check: Option<CargoVersionCheck>,
+    pub(crate) cargo_version_check_tag_prefix: Option<String>,
+    pub(crate) version_string_template: Option<String>,
+    pub(crate) embed_link_section: bool,
+    pub(crate) submodules: bool,
+    pub(crate) additional_repositories: Vec<AdditionalRepository>,
+}
+
+/// What [`init_proxy_build!`](crate::init_proxy_build) should do if the git tag doesn't match the `Cargo.toml` version.
+///
+/// See [`ProxyBuildConfig::cargo_version_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnVersionMismatch {
+    /// Emit a cargo warning but continue the build.
+    Warn,
+
+    /// Fail the build.
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CargoVersionCheck {
+    pub(crate) crate_dir: PathBuf,
+    pub(crate) on_mismatch: OnVersionMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AdditionalRepository {
+    pub(crate) const_name: String,
+    pub(crate) location: RepositoryLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RepositoryLocation {
+    Path(PathBuf),
+    EnvVar(String),
+}
+
+impl ProxyBuildConfig {
+    /// Creates the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the version information from the git repository containing `repository_dir` instead of the one containing the proxy crate.
+    ///
+    /// This is mostly useful together with named proxies, where one proxy crate exposes the version information of several
+    /// repositories, see [`init_proxy_build!`](crate::init_proxy_build). If there is no git repository, the snapshot file is
+    /// looked up in `repository_dir` as well.
+    ///
+    /// Relative paths are relative to the directory of the proxy crate's `Cargo.toml`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().repository_dir("../server");
+    /// ```
+    pub fn repository_dir(mut self, repository_dir: impl Into<PathBuf>) -> Self {
+        self.repository_dir = Some(repository_dir.into());
+        self
+    }
+
+    /// Stops the search for the git repository at `ceiling_dir`, like git's `GIT_CEILING_DIRECTORIES` does.
+    ///
+    /// The repository is searched for in the directory of the proxy crate (or [`ProxyBuildConfig::repository_dir`]) and its parents.
+    /// If that directory is inside of `ceiling_dir`, a repository in `ceiling_dir` or one of its parents is ignored, as if there
+    /// was no repository. This keeps a crate that is vendored into an unrelated repository, e.g. a home directory that is tracked
+    /// with git, from reporting that repository's version. Can be called several times to add more ceiling directories.
+    ///
+    /// `GIT_CEILING_DIRECTORIES` itself is honored as well, unless [`ProxyBuildConfig::repository_dir`] is set.
+    /// Neither applies if `GIT_DIR` is set, because there is no search then.
+    ///
+    /// Relative paths are relative to the directory of the proxy crate's `Cargo.toml`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// // Ignore repositories above the workspace root, for a proxy crate in `<workspace root>/version_proxy`
+    /// let config = ProxyBuildConfig::new().ceiling_dir("../..");
+    /// ```
+    pub fn ceiling_dir(mut self, ceiling_dir: impl Into<PathBuf>) -> Self {
+        self.ceiling_dirs.push(ceiling_dir.into());
+        self
+    }
+
+    /// Only uses a discovered git repository if its index tracks at least one file in the directory of the proxy crate
+    /// (or [`ProxyBuildConfig::repository_dir`]).
+    ///
+    /// Other repositories are ignored, as if there was no repository, and the snapshot file, environment variable overrides
+    /// and [`ProxyBuildConfig::cargo_manifest_fallback`] are used instead. This keeps a crate that is vendored into an unrelated
+    /// repository from reporting that repository's version, without having to know where it could be vendored to.
+    /// Adding the proxy crate's files to the index, e.g. with `git add`, makes the repository count.
+    ///
+    /// This doesn't apply if `GIT_DIR` is set, because then the repository was explicitly selected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().require_tracked(true);
+    /// ```
+    pub fn require_tracked(mut self, require_tracked: bool) -> Self {
+        self.require_tracked = require_tracked;
+        self
+    }
+
+    /// Falls back to the package version from the `Cargo.toml` in `crate_dir` if no version information is available from git.
+    ///
+    /// The fallback is only used if neither git, a snapshot file, nor environment variable overrides provided any version information.
+    /// The resulting [`GitInfo`](crate::GitInfo) has [`VersionSource::CargoManifest`](crate::VersionSource::CargoManifest)
+    /// as its `source`, the package version as its tag and an empty commit id.
+    ///
+    /// `crate_dir` is the directory containing the `Cargo.toml` of the crate whose version should be used, e.g. your main crate.
+    /// Relative paths are relative to the directory of the proxy crate's `Cargo.toml`.
+    pub fn cargo_manifest_fallback(mut self, crate_dir: impl Into<PathBuf>) -> Self {
+        self.cargo_manifest_fallback = Some(crate_dir.into());
+        self
+    }
+
+    /// Checks that the git tag matches the package version in the `Cargo.toml` in `crate_dir`.
+    ///
+    /// This catches the common mistake of forgetting to bump the `version` in `Cargo.toml` before tagging a release.
+    /// The check only happens if HEAD is exactly on a tag, i.e. `commits_since_tag` is `0`. A leading `v` is stripped from the tag
+    /// before comparing it with the `Cargo.toml` version, use [`ProxyBuildConfig::cargo_version_check_tag_prefix`] to change that prefix.
+    /// The two can be called in any order.
+    ///
+    /// `crate_dir` is the directory containing the `Cargo.toml` of the crate whose version should be checked, e.g. your main crate.
+    /// Relative paths are relative to the directory of the proxy crate's `Cargo.toml`.
+    /// If its version can't be read, the build fails regardless of `on_mismatch`, because that's a configuration error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::{OnVersionMismatch, ProxyBuildConfig};
+    ///
+    /// let config = ProxyBuildConfig::new().cargo_version_check("..", OnVersionMismatch::Fail);
+    /// ```
+    pub fn cargo_version_check(
+        mut self,
+        crate_dir: impl Into<PathBuf>,
+        on_mismatch: OnVersionMismatch,
+    ) -> Self {
+        self.cargo_version_check = Some(CargoVersionCheck {
+            crate_dir: crate_dir.into(),
+            on_mismatch,
+        });
+        self
+    }
+
+    /// Sets the prefix that is stripped from the tag before comparing it with the `Cargo.toml` version
+    /// in [`ProxyBuildConfig::cargo_version_check`]. The default is `"v"`.
+    ///
+    /// This has no effect unless [`ProxyBuildConfig::cargo_version_check`] is called as well, before or after this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::{OnVersionMismatch, ProxyBuildConfig};
+    ///
+    /// let config = ProxyBuildConfig::new()
+    ///     .cargo_version_check("..", OnVersionMismatch::Fail)
+    ///     .cargo_version_check_tag_prefix("release-");
+    /// ```
+    pub fn cargo_version_check_tag_prefix(mut self, tag_prefix: impl Into<String>) -> Self {
+        self.cargo_version_check_tag_prefix = Some(tag_prefix.into());
+        self
+    }
+
+    /// Sets the template for the `VERSION_STRING` constant generated by [`init_proxy_lib!`](crate::init_proxy_lib).
+    ///
+    /// See [`GitInfo::format_with`](crate::GitInfo::format_with) for the template syntax. Since the template is evaluated
+    /// in the build script, the `{full}` and `{branch}` placeholders are available if the version information came from git.
+    /// If no template is set, `VERSION_STRING` uses the [`Display`](std::fmt::Display) format of [`GitInfo`](crate::GitInfo).
+    /// If no version information is available, `VERSION_STRING` is `"unknown"`.
+    ///
+    /// An invalid template fails the build.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().version_string_template("{tag}-{distance}-{short}{?branch} ({branch}){/branch}");
+    /// ```
+    pub fn version_string_template(mut self, template: impl Into<String>) -> Self {
+        self.version_string_template = Some(template.into());
+        self
+    }
+
+    /// Embeds the version information into a dedicated section of the compiled binary, so that it can be read
+    /// from the binary file without running it, e.g. with `read_link_section`.
+    ///
+    /// If enabled, [`init_proxy_lib!`](crate::init_proxy_lib) generates a static in the `.git2version` link section
+    /// (`__DATA,__git2version` on Apple platforms) containing a small header and the version information as JSON.
+    ///
+    /// Note that the generated static uses `#[unsafe(link_section)]`, so this doesn't work if the proxy crate
+    /// forbids `unsafe_code`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().embed_link_section(true);
+    /// ```
+    pub fn embed_link_section(mut self, embed_link_section: bool) -> Self {
+        self.embed_link_section = embed_link_section;
+        self
+    }
+
+    /// Also looks up the version information of the repository's submodules.
+    ///
+    /// If enabled, the `SUBMODULES` constant generated by [`init_proxy_lib!`](crate::init_proxy_lib) contains a
+    /// [`SubmoduleInfo`](crate::SubmoduleInfo) for each submodule, see [`get_submodule_info`](crate::get_submodule_info).
+    /// Otherwise, or if the version information didn't come from a git repository, `SUBMODULES` is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().submodules(true);
+    /// ```
+    pub fn submodules(mut self, submodules: bool) -> Self {
+        self.submodules = submodules;
+        self
+    }
+
+    /// Also looks up the version information of the git repository containing `repository_dir`, and makes it
+    /// available as a constant named `const_name` in the proxy crate.
+    ///
+    /// This is useful if your build depends on other repositories, e.g. a sibling checkout that is referenced by path.
+    /// [`init_proxy_lib!`](crate::init_proxy_lib) generates `pub const {const_name}: Option<GitInfo>` for each additional repository.
+    /// It is `None` if there is no git repository or if there was an error looking up the version information.
+    /// The build script reruns when anything in the additional repository changes, just like for the proxy crate's repository.
+    ///
+    /// Additional repositories only report what's in git. Environment variable overrides, snapshot files and the
+    /// other settings of this configuration only apply to the proxy crate's repository. If you need those,
+    /// use a named proxy with [`ProxyBuildConfig::repository_dir`] instead, see [`init_proxy_build!`](crate::init_proxy_build).
+    ///
+    /// `const_name` must be a valid Rust identifier that doesn't conflict with the other constants generated by `init_proxy_lib!`,
+    /// otherwise the build fails. Relative paths are relative to the directory of the proxy crate's `Cargo.toml`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().additional_repository("SIBLING_GITINFO", "../../sibling");
+    /// ```
+    pub fn additional_repository(
+        mut self,
+        const_name: impl Into<String>,
+        repository_dir: impl Into<PathBuf>,
+    ) -> Self {
+        self.additional_repositories.push(AdditionalRepository {
+            const_name: const_name.into(),
+            location: RepositoryLocation::Path(repository_dir.into()),
+        });
+        self
+    }
+
+    /// Like [`ProxyBuildConfig::additional_repository`], but reads the directory from the environment variable `env_var`
+    /// when the build script runs.
+    ///
+    /// The build script reruns when the environment variable changes. If it isn't set, the constant is `None` and a warning is emitted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::ProxyBuildConfig;
+    ///
+    /// let config = ProxyBuildConfig::new().additional_repository_from_env("SIBLING_GITINFO", "SIBLING_DIR");
+    /// ```
+    pub fn additional_repository_from_env(
+        mut self,
+        const_name: impl Into<String>,
+        env_var: impl Into<String>,
+    ) -> Self {
+        self.additional_repositories.push(AdditionalRepository {
+            const_name: const_name.into(),
+            location: RepositoryLocation::EnvVar(env_var.into()),
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_version_check_tag_prefix_in_any_order() {
+        let check_first = ProxyBuildConfig::new()
+            .cargo_version_check("..", OnVersionMismatch::Warn)
+            .cargo_version_check_tag_prefix("release-");
+        let prefix_first = ProxyBuildConfig::new()
+            .cargo_version_check_tag_prefix("release-")
+            .cargo_version_check("..", OnVersionMismatch::Warn);
+        assert_eq!(check_first, prefix_first);
+        assert_eq!(
+            Some("release-"),
+            check_first.cargo_version_check_tag_prefix.as_deref()
+        );
+    }
+}
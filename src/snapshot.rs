@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use super::gitinfo_owned::GitInfoOwned;
+
+/// Name of the snapshot file that [`init_proxy_build!`](crate::init_proxy_build) reads
+/// when it doesn't find a git repository.
+///
+/// The file is expected next to the `Cargo.toml` of the proxy crate.
+pub const SNAPSHOT_FILE_NAME: &str = ".git2version.json";
+
+/// Writes a snapshot of the given version information into the proxy crate directory.
+///
+/// The snapshot is written to a file named [`SNAPSHOT_FILE_NAME`] in `proxy_crate_dir`, i.e. next to the
+/// proxy crate's `Cargo.toml`. If [`init_proxy_build!`](crate::init_proxy_build) later doesn't find a
+/// git repository, e.g. when building from a source tarball created by `cargo package` or from vendored
+/// sources, it reads the version information from that file instead.
+///
+/// Remember to regenerate the snapshot whenever you create a source distribution, otherwise it will
+/// report an outdated version.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::path::Path;
+///
+/// let repo = git2version::git2::Repository::discover("version_proxy").unwrap();
+/// let git_info = git2version::get_git_info(&repo).unwrap();
+/// git2version::write_snapshot(Path::new("version_proxy"), &git_info).unwrap();
+/// ```
+pub fn write_snapshot(proxy_crate_dir: &Path, git_info: &GitInfoOwned) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(proxy_crate_dir.join(SNAPSHOT_FILE_NAME))?);
+    serde_json::to_writer_pretty(&mut writer, git_info)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Reads a snapshot previously written by [`write_snapshot`] from the proxy crate directory.
+///
+/// Returns `Ok(None)` if there is no snapshot file.
+///
+/// # Errors
+///
+/// Returns an [`io::Error`] if the snapshot file exists but can't be read or parsed.
+pub fn read_snapshot(proxy_crate_dir: &Path) -> io::Result<Option<GitInfoOwned>> {
+    let file = match File::open(proxy_crate_dir.join(SNAPSHOT_FILE_NAME)) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let git_info = serde_json::from_reader(BufReader::new(file))?;
+    Ok(Some(git_info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VersionSource;
+    use crate::gitinfo_owned::TagInfoOwned;
+    use tempdir::TempDir;
+
+    #[test]
+    fn no_snapshot_file() {
+        let dir = TempDir::new("test").unwrap();
+        assert_eq!(None, read_snapshot(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn roundtrip_with_tag() {
+        let dir = TempDir::new("test").unwrap();
+        let git_info = GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3-alpha".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: "abcdef1234".to_string(),
+            modified: true,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        write_snapshot(dir.path(), &git_info).unwrap();
+        assert_eq!(Some(git_info), read_snapshot(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn roundtrip_without_tag() {
+        let dir = TempDir::new("test").unwrap();
+        let git_info = GitInfoOwned {
+            tag_info: None,
+            commit_id: "abcdef1234".to_string(),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        write_snapshot(dir.path(), &git_info).unwrap();
+        assert_eq!(Some(git_info), read_snapshot(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn overwrites_existing_snapshot() {
+        let dir = TempDir::new("test").unwrap();
+        let old = GitInfoOwned {
+            tag_info: None,
+            commit_id: "abcdef1234".to_string(),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        let new = GitInfoOwned {
+            tag_info: None,
+            commit_id: "0123456789".to_string(),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        write_snapshot(dir.path(), &old).unwrap();
+        write_snapshot(dir.path(), &new).unwrap();
+        assert_eq!(Some(new), read_snapshot(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn invalid_snapshot_file() {
+        let dir = TempDir::new("test").unwrap();
+        std::fs::write(dir.path().join(SNAPSHOT_FILE_NAME), "not json").unwrap();
+        assert!(read_snapshot(dir.path()).is_err());
+    }
+}
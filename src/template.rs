@@ -0,0 +1,614 @@
+use core::fmt::{self, Display, Formatter};
+
+use super::gitinfo::GitInfo;
+
+/// An error in a version string template, see [`GitInfo::format_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TemplateError {
+    /// Byte offset in the template where the error was found.
+    pub position: usize,
+
+    /// What is wrong with the template.
+    pub kind: TemplateErrorKind,
+}
+
+/// The kind of a [`TemplateError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplateErrorKind {
+    /// A `{` without a matching `}`.
+    UnclosedPlaceholder,
+
+    /// A `}` that doesn't close a placeholder. Use `}}` for a literal `}`.
+    UnmatchedClosingBrace,
+
+    /// A placeholder name that isn't known.
+    UnknownPlaceholder,
+
+    /// A `{?name}` or `{!name}` section without a matching `{/name}`.
+    UnclosedSection,
+
+    /// A `{/name}` that doesn't close the innermost open section.
+    UnmatchedSectionEnd,
+
+    /// A placeholder that is only available when the template is rendered by the build script,
+    /// i.e. `{full}` or `{branch}` passed to [`GitInfo::format_with`].
+    BuildScriptOnlyPlaceholder,
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            TemplateErrorKind::UnclosedPlaceholder => "'{' without matching '}'",
+            TemplateErrorKind::UnmatchedClosingBrace => "'}' without matching '{'",
+            TemplateErrorKind::UnknownPlaceholder => "unknown placeholder",
+            TemplateErrorKind::UnclosedSection => "section without matching '{/name}'",
+            TemplateErrorKind::UnmatchedSectionEnd => {
+                "'{/name}' doesn't match the innermost open section"
+            }
+            TemplateErrorKind::BuildScriptOnlyPlaceholder => {
+                "placeholder is only available in ProxyBuildConfig::version_string_template"
+            }
+        };
+        write!(
+            f,
+            "Invalid version template at position {}: {}",
+            self.position, message
+        )
+    }
+}
+
+impl core::error::Error for TemplateError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placeholder {
+    Tag,
+    Distance,
+    Short,
+    Full,
+    Branch,
+    Dirty,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tag" => Some(Self::Tag),
+            "distance" => Some(Self::Distance),
+            "short" => Some(Self::Short),
+            "full" => Some(Self::Full),
+            "branch" => Some(Self::Branch),
+            "dirty" => Some(Self::Dirty),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is only known in the build script and not stored in [`GitInfo`].
+    fn is_build_script_only(self) -> bool {
+        matches!(self, Self::Full | Self::Branch)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'t> {
+    Literal(&'t str),
+    Value(Placeholder),
+    IfPresent(Placeholder, &'t str),
+    SectionStart {
+        placeholder: Placeholder,
+        inverted: bool,
+    },
+    SectionEnd(Placeholder),
+}
+
+/// Splits the next token off the beginning of `template`. `offset` is the position of `template` in the full template
+/// and is only used for error messages.
+fn next_token(template: &str, offset: usize) -> Result<(Token<'_>, &str), TemplateError> {
+    let error = |position: usize, kind| TemplateError {
+        position: offset + position,
+        kind,
+    };
+    if let Some(rest) = template.strip_prefix("{{") {
+        return Ok((Token::Literal("{"), rest));
+    }
+    if let Some(rest) = template.strip_prefix("}}") {
+        return Ok((Token::Literal("}"), rest));
+    }
+    if template.starts_with('}') {
+        return Err(error(0, TemplateErrorKind::UnmatchedClosingBrace));
+    }
+    let Some(placeholder) = template.strip_prefix('{') else {
+        let end = template.find(['{', '}']).unwrap_or(template.len());
+        return Ok((Token::Literal(&template[..end]), &template[end..]));
+    };
+    let end = placeholder
+        .find('}')
+        .ok_or(error(0, TemplateErrorKind::UnclosedPlaceholder))?;
+    let (placeholder, rest) = (&placeholder[..end], &placeholder[end + 1..]);
+    if placeholder.contains('{') {
+        return Err(error(0, TemplateErrorKind::UnclosedPlaceholder));
+    }
+    let parse_name = |name: &str| {
+        Placeholder::from_name(name).ok_or(error(1, TemplateErrorKind::UnknownPlaceholder))
+    };
+    let token = if let Some(name) = placeholder.strip_prefix('?') {
+        Token::SectionStart {
+            placeholder: parse_name(name)?,
+            inverted: false,
+        }
+    } else if let Some(name) = placeholder.strip_prefix('!') {
+        Token::SectionStart {
+            placeholder: parse_name(name)?,
+            inverted: true,
+        }
+    } else if let Some(name) = placeholder.strip_prefix('/') {
+        Token::SectionEnd(parse_name(name)?)
+    } else if let Some((name, text)) = placeholder.split_once(':') {
+        Token::IfPresent(parse_name(name)?, text)
+    } else {
+        Token::Value(parse_name(placeholder)?)
+    };
+    Ok((token, rest))
+}
+
+/// Checks that the template is valid, i.e. that it only has known placeholders and that all sections are closed.
+fn validate(template: &str) -> Result<(), TemplateError> {
+    let rest = validate_sequence(template, template, None)?;
+    assert!(rest.is_empty());
+    Ok(())
+}
+
+/// Validates tokens until the end of `section` (or the end of the template if `section` is `None`)
+/// and returns the remaining template after the section end.
+fn validate_sequence<'t>(
+    full_template: &'t str,
+    mut template: &'t str,
+    section: Option<(Placeholder, usize)>,
+) -> Result<&'t str, TemplateError> {
+    loop {
+        let offset = full_template.len() - template.len();
+        if template.is_empty() {
+            return match section {
+                None => Ok(template),
+                Some((_, section_start)) => Err(TemplateError {
+                    position: section_start,
+                    kind: TemplateErrorKind::UnclosedSection,
+                }),
+            };
+        }
+        let (token, rest) = next_token(template, offset)?;
+        template = match token {
+            Token::SectionStart { placeholder, .. } => {
+                validate_sequence(full_template, rest, Some((placeholder, offset)))?
+            }
+            Token::SectionEnd(placeholder) => {
+                return match section {
+                    Some((section_placeholder, _)) if section_placeholder == placeholder => {
+                        Ok(rest)
+                    }
+                    _ => Err(TemplateError {
+                        position: offset,
+                        kind: TemplateErrorKind::UnmatchedSectionEnd,
+                    }),
+                };
+            }
+            Token::Literal(_) | Token::Value(_) | Token::IfPresent(..) => rest,
+        };
+    }
+}
+
+/// Checks that a valid template doesn't use placeholders that are only available in the build script.
+fn check_runtime_placeholders(template: &str) -> Result<(), TemplateError> {
+    let mut rest = template;
+    while !rest.is_empty() {
+        let offset = template.len() - rest.len();
+        let (token, next) = next_token(rest, offset)?;
+        let placeholder = match token {
+            Token::Literal(_) => None,
+            Token::Value(placeholder)
+            | Token::IfPresent(placeholder, _)
+            | Token::SectionStart { placeholder, .. }
+            | Token::SectionEnd(placeholder) => Some(placeholder),
+        };
+        if placeholder.is_some_and(Placeholder::is_build_script_only) {
+            return Err(TemplateError {
+                position: offset + 1,
+                kind: TemplateErrorKind::BuildScriptOnlyPlaceholder,
+            });
+        }
+        rest = next;
+    }
+    Ok(())
+}
+
+/// The values that placeholders in a template get replaced with. `None` means that the value isn't present.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TemplateValues<'v> {
+    pub(crate) tag: Option<&'v str>,
+    pub(crate) distance: Option<u32>,
+    pub(crate) short: Option<&'v str>,
+    pub(crate) full: Option<&'v str>,
+    pub(crate) branch: Option<&'v str>,
+    pub(crate) dirty: bool,
+}
+
+impl<'v> TemplateValues<'v> {
+    pub(crate) fn from_git_info(info: &'v GitInfo<'_, '_>) -> Self {
+        Self {
+            tag: info.tag_info.map(|tag_info| tag_info.tag),
+            distance: info.tag_info.map(|tag_info| tag_info.commits_since_tag),
+            short: Some(info.commit_id).filter(|commit_id| !commit_id.is_empty()),
+            full: None,
+            branch: None,
+            dirty: info.modified,
+        }
+    }
+
+    fn is_present(&self, placeholder: Placeholder) -> bool {
+        match placeholder {
+            Placeholder::Tag => self.tag.is_some(),
+            Placeholder::Distance => self.distance.is_some(),
+            Placeholder::Short => self.short.is_some(),
+            Placeholder::Full => self.full.is_some(),
+            Placeholder::Branch => self.branch.is_some(),
+            Placeholder::Dirty => self.dirty,
+        }
+    }
+
+    fn write_value(&self, f: &mut Formatter<'_>, placeholder: Placeholder) -> fmt::Result {
+        match placeholder {
+            Placeholder::Tag => f.write_str(self.tag.unwrap_or_default()),
+            Placeholder::Distance => match self.distance {
+                Some(distance) => write!(f, "{distance}"),
+                None => Ok(()),
+            },
+            Placeholder::Short => f.write_str(self.short.unwrap_or_default()),
+            Placeholder::Full => f.write_str(self.full.unwrap_or_default()),
+            Placeholder::Branch => f.write_str(self.branch.unwrap_or_default()),
+            Placeholder::Dirty => f.write_str(if self.dirty { "dirty" } else { "" }),
+        }
+    }
+}
+
+/// Renders a [`GitInfo`] using a template. Created by [`GitInfo::format_with`].
+#[derive(Clone, Copy, Debug)]
+pub struct FormatWith<'v, 't> {
+    values: TemplateValues<'v>,
+    template: &'t str,
+}
+
+impl<'v, 't> FormatWith<'v, 't> {
+    pub(crate) fn new(
+        values: TemplateValues<'v>,
+        template: &'t str,
+    ) -> Result<Self, TemplateError> {
+        validate(template)?;
+        Ok(Self { values, template })
+    }
+}
+
+impl Display for FormatWith<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut template = self.template;
+        // Number of nested sections we're currently skipping because their condition wasn't met
+        let mut skip_depth = 0usize;
+        while !template.is_empty() {
+            let offset = self.template.len() - template.len();
+            let (token, rest) = next_token(template, offset)
+                .expect("The template was validated when creating the FormatWith instance");
+            template = rest;
+            match token {
+                Token::SectionStart {
+                    placeholder,
+                    inverted,
+                } => {
+                    if skip_depth > 0 || self.values.is_present(placeholder) == inverted {
+                        skip_depth += 1;
+                    }
+                }
+                Token::SectionEnd(_) => {
+                    skip_depth = skip_depth.saturating_sub(1);
+                }
+                _ if skip_depth > 0 => {}
+                Token::Literal(literal) => f.write_str(literal)?,
+                Token::Value(placeholder) => self.values.write_value(f, placeholder)?,
+                Token::IfPresent(placeholder, text) => {
+                    if self.values.is_present(placeholder) {
+                        f.write_str(text)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b> GitInfo<'a, 'b> {
+    /// Renders this version information using a custom template.
+    ///
+    /// The template can contain the following placeholders:
+    ///
+    /// - `{tag}` - The tag name. Missing if there is no tag.
+    /// - `{distance}` - The number of commits since the tag. Missing if there is no tag.
+    /// - `{short}` - The shortened commit id. Missing if the commit id is empty.
+    /// - `{dirty}` - The text `dirty` if the working directory was modified, missing otherwise.
+    ///
+    /// `ProxyBuildConfig::version_string_template` additionally supports `{full}` (the full commit id) and
+    /// `{branch}` (the checked out branch). [`GitInfo`] doesn't store them, so this method rejects templates
+    /// that use them.
+    ///
+    /// Missing values are rendered as empty strings. To only render some text if a value is present, use
+    ///
+    /// - `{name:text}` - Renders `text` if `name` is present, e.g. `{dirty:-dirty}`.
+    /// - `{?name}...{/name}` - Renders the section, which can contain other placeholders, if `name` is present.
+    /// - `{!name}...{/name}` - Renders the section if `name` is missing.
+    ///
+    /// Use `{{` and `}}` for literal braces.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TemplateError`] if the template is invalid, e.g. contains unknown placeholders or unclosed sections,
+    /// or if it uses `{full}` or `{branch}`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use git2version::{GitInfo, TagInfo, VersionSource};
+    ///
+    /// let info = GitInfo {
+    ///     tag_info: Some(TagInfo {
+    ///         tag: "v1.2.3",
+    ///         commits_since_tag: 5,
+    ///     }),
+    ///     commit_id: "abcdef1234",
+    ///     modified: true,
+    ///     source: VersionSource::Git,
+    ///     worktree: None,
+    /// };
+    /// let template = "{?tag}{tag}-{distance}{/tag}{!tag}untagged{/tag}-{short}{dirty:-dirty}";
+    /// assert_eq!(info.format_with(template).unwrap().to_string(), "v1.2.3-5-abcdef1234-dirty");
+    /// ```
+    pub fn format_with<'t>(&self, template: &'t str) -> Result<FormatWith<'_, 't>, TemplateError> {
+        let format_with = FormatWith::new(TemplateValues::from_git_info(self), template)?;
+        check_runtime_placeholders(template)?;
+        Ok(format_with)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TagInfo, VersionSource};
+
+    fn values() -> TemplateValues<'static> {
+        TemplateValues {
+            tag: Some("v1.2.3"),
+            distance: Some(5),
+            short: Some("abcdef1234"),
+            full: Some("abcdef1234567890abcdef1234567890abcdef12"),
+            branch: Some("main"),
+            dirty: true,
+        }
+    }
+
+    fn render(values: TemplateValues, template: &str) -> String {
+        FormatWith::new(values, template).unwrap().to_string()
+    }
+
+    fn error(template: &str) -> TemplateError {
+        FormatWith::new(values(), template).unwrap_err()
+    }
+
+    #[test]
+    fn empty_template() {
+        assert_eq!("", render(values(), ""));
+    }
+
+    #[test]
+    fn literal_only() {
+        assert_eq!("version", render(values(), "version"));
+    }
+
+    #[test]
+    fn all_placeholders() {
+        assert_eq!(
+            "v1.2.3 5 abcdef1234 abcdef1234567890abcdef1234567890abcdef12 main dirty",
+            render(values(), "{tag} {distance} {short} {full} {branch} {dirty}")
+        );
+    }
+
+    #[test]
+    fn missing_values_are_empty() {
+        assert_eq!(
+            "[][][][][][]",
+            render(
+                TemplateValues::default(),
+                "[{tag}][{distance}][{short}][{full}][{branch}][{dirty}]"
+            )
+        );
+    }
+
+    #[test]
+    fn if_present() {
+        assert_eq!("1.0-dirty", render(values(), "1.0{dirty:-dirty}"));
+        assert_eq!(
+            "1.0",
+            render(
+                TemplateValues {
+                    dirty: false,
+                    ..values()
+                },
+                "1.0{dirty:-dirty}"
+            )
+        );
+        assert_eq!("on branch", render(values(), "{branch:on branch}"));
+        assert_eq!("", render(TemplateValues::default(), "{branch:on branch}"));
+    }
+
+    #[test]
+    fn sections() {
+        let template = "{?tag}{tag}+{distance}{/tag}{!tag}unknown{/tag}.g{short}";
+        assert_eq!("v1.2.3+5.gabcdef1234", render(values(), template));
+        assert_eq!(
+            "unknown.gabcdef1234",
+            render(
+                TemplateValues {
+                    tag: None,
+                    distance: None,
+                    ..values()
+                },
+                template
+            )
+        );
+    }
+
+    #[test]
+    fn nested_sections() {
+        let template = "{?tag}{tag}{?branch}@{branch}{!dirty} (clean){/dirty}{/branch}{/tag}";
+        assert_eq!("v1.2.3@main", render(values(), template));
+        assert_eq!(
+            "v1.2.3@main (clean)",
+            render(
+                TemplateValues {
+                    dirty: false,
+                    ..values()
+                },
+                template
+            )
+        );
+        assert_eq!(
+            "v1.2.3",
+            render(
+                TemplateValues {
+                    branch: None,
+                    dirty: false,
+                    ..values()
+                },
+                template
+            )
+        );
+        assert_eq!(
+            "",
+            render(
+                TemplateValues {
+                    tag: None,
+                    dirty: false,
+                    ..values()
+                },
+                template
+            )
+        );
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!("{v1.2.3}", render(values(), "{{{tag}}}"));
+        assert_eq!("{tag}", render(values(), "{{tag}}"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            TemplateError {
+                position: 3,
+                kind: TemplateErrorKind::UnclosedPlaceholder
+            },
+            error("ab {tag")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 3,
+                kind: TemplateErrorKind::UnmatchedClosingBrace
+            },
+            error("ab } cd")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 4,
+                kind: TemplateErrorKind::UnknownPlaceholder
+            },
+            error("ab {unknown}")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 3,
+                kind: TemplateErrorKind::UnclosedSection
+            },
+            error("ab {?tag}cd")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 3,
+                kind: TemplateErrorKind::UnmatchedSectionEnd
+            },
+            error("ab {/tag}")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 14,
+                kind: TemplateErrorKind::UnmatchedSectionEnd
+            },
+            error("{?tag}{?dirty}{/tag}{/dirty}")
+        );
+        assert_eq!(
+            TemplateError {
+                position: 0,
+                kind: TemplateErrorKind::UnclosedPlaceholder
+            },
+            error("{ta{g}")
+        );
+    }
+
+    #[test]
+    fn format_with_git_info() {
+        let info = GitInfo {
+            tag_info: None,
+            commit_id: "abcdef1234",
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        };
+        assert_eq!(
+            "untagged-abcdef1234",
+            info.format_with("{tag:tagged}{!tag}untagged{/tag}-{short}{dirty:-dirty}")
+                .unwrap()
+                .to_string()
+        );
+        // The full commit id and the branch are only known in the build script
+        for (template, position) in [
+            ("v-{full}", 3),
+            ("{short}{branch:-}", 8),
+            ("{?full}x{/full}", 1),
+            ("{!tag}{!branch}x{/branch}{/tag}", 7),
+        ] {
+            assert_eq!(
+                TemplateError {
+                    position,
+                    kind: TemplateErrorKind::BuildScriptOnlyPlaceholder
+                },
+                info.format_with(template).unwrap_err()
+            );
+        }
+        // Syntax errors are reported first
+        assert_eq!(
+            TemplateErrorKind::UnclosedSection,
+            info.format_with("{full}{?tag}").unwrap_err().kind
+        );
+
+        let info = GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3",
+                commits_since_tag: 0,
+            }),
+            commit_id: "",
+            modified: false,
+            source: VersionSource::CargoManifest,
+            worktree: None,
+        };
+        assert_eq!(
+            "v1.2.3+0",
+            info.format_with("{tag}+{distance}{short:.g}{short}")
+                .unwrap()
+                .to_string()
+        );
+    }
+}
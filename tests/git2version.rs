@@ -5,7 +5,8 @@ use std::path::Path;
 use std::process::Command;
 use tempdir::TempDir;
 
-use git2version::{COMMIT_ID_SHORT_HASH_LENGTH, GitInfo, TagInfo};
+use git2version::{COMMIT_ID_SHORT_HASH_LENGTH, GitInfo, TagInfo, VersionSource};
+use git2version::{GitInfoOwned, SubmoduleInfoOwned, TagInfoOwned};
 
 const FILENAME: &str = "some_file";
 
@@ -121,6 +122,8 @@ fn with_initial_commit_notmodified() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -137,6 +140,8 @@ fn with_initial_commit_modified() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -154,6 +159,8 @@ fn with_initial_commit_modified_staged() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -169,6 +176,8 @@ fn with_some_commits_but_no_tags_notmodified() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -185,6 +194,8 @@ fn with_some_commits_but_no_tags_modified() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -202,6 +213,8 @@ fn with_some_commits_but_no_tags_modified_staged() {
             tag_info: None,
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -220,6 +233,8 @@ fn on_tag_notmodified() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -239,6 +254,8 @@ fn on_tag_modified() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -259,6 +276,8 @@ fn on_tag_modified_staged() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -277,6 +296,8 @@ fn after_tag_notmodified() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -296,6 +317,8 @@ fn after_tag_modified() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -316,6 +339,8 @@ fn after_tag_modified_staged() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: true,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -350,6 +375,8 @@ fn detached_head_state() {
             tag_info: None,
             commit_id: &first_commit_id,
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -400,6 +427,8 @@ fn tag_with_special_characters() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -420,6 +449,8 @@ fn tag_without_v_prefix() {
             }),
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         }),
     );
 }
@@ -472,7 +503,7 @@ edition = "2021"
 version = "0.0.0"
 
 [dependencies]
-git2version = {{path = "{path_to_git2version_crate}"}}
+git2version = {{path = "{path_to_git2version_crate}", features=["serde"]}}
 
 [build-dependencies]
 git2version = {{path = "{path_to_git2version_crate}", features=["build"]}}
@@ -508,10 +539,1347 @@ fn main() {{
             }),
             commit_id: &head_commit_id(&repo),
             modified: false,
+            source: VersionSource::Git,
+            worktree: None,
         })
     );
 }
 
+/// Creates a linked worktree named `name` for `repo` and returns the directory containing it
+fn create_worktree(repo: &Repository, name: &str) -> TempDir {
+    let dir = TempDir::new("package-version-test-worktree").unwrap();
+    repo.worktree(name, &dir.path().join(name), None).unwrap();
+    dir
+}
+
+#[test]
+fn worktree() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3");
+    let worktree_dir = create_worktree(&repo, "feature");
+    let worktree_path = worktree_dir.path().join("feature");
+    let commit_id = head_commit_id(&repo);
+
+    let expected = |modified| GitInfo {
+        tag_info: Some(TagInfo {
+            tag: "v1.2.3",
+            commits_since_tag: 5,
+        }),
+        commit_id: &commit_id,
+        modified,
+        source: VersionSource::Git,
+        worktree: Some("feature"),
+    };
+    run_version_test_project(&worktree_path, Some(expected(false)));
+
+    // Changes in the worktree are noticed
+    std::fs::write(worktree_path.join(FILENAME), "changed in worktree").unwrap();
+    run_version_test_project(&worktree_path, Some(expected(true)));
+
+    // The main worktree isn't affected. Cargo would reuse the build script output of the other worktree
+    // if they shared a target directory, because it identifies path dependencies by their path relative to the workspace.
+    let target_dir = project_dir.path().join("target");
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[("CARGO_TARGET_DIR", target_dir.to_str().unwrap())],
+        Some(GitInfo {
+            worktree: None,
+            modified: false,
+            ..expected(false)
+        }),
+    );
+}
+
+#[test]
+fn worktree_notices_new_tags() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3");
+    let worktree_dir = create_worktree(&repo, "feature");
+    let worktree_path = worktree_dir.path().join("feature");
+    let commit_id = head_commit_id(&repo);
+
+    let expected = |tag, commits_since_tag| {
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag,
+                commits_since_tag,
+            }),
+            commit_id: &commit_id,
+            modified: false,
+            source: VersionSource::Git,
+            worktree: Some("feature"),
+        })
+    };
+    run_version_test_project(&worktree_path, expected("v1.2.3", 5));
+
+    // Tags are stored in the main repository's .git directory, not in the worktree's
+    create_tag(&repo, "v2.0.0");
+    run_version_test_project(&worktree_path, expected("v2.0.0", 0));
+}
+
+#[test]
+fn git_dir_and_work_tree_env() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3");
+    let commit_id = head_commit_id(&repo);
+    // Move the .git directory out of the project, so that it can only be found through the environment variables
+    let git_dir = TempDir::new("package-version-test-git-dir").unwrap();
+    let git_dir_path = git_dir.path().join("repo.git");
+    std::fs::rename(project_dir.path().join(".git"), &git_dir_path).unwrap();
+
+    run_version_test_project(project_dir.path(), None);
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[
+            ("GIT_DIR", git_dir_path.to_str().unwrap()),
+            ("GIT_WORK_TREE", project_dir.path().to_str().unwrap()),
+        ],
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3",
+                commits_since_tag: 5,
+            }),
+            commit_id: &commit_id,
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn git_dir_bare_repository() {
+    let project_dir = make_version_test_project();
+    let source_dir = TempDir::new("package-version-test-source").unwrap();
+    let source_repo = create_repo(source_dir.path());
+    create_some_commits_and_a_tag(&source_repo, "v2.0.0");
+    let bare_dir = TempDir::new("package-version-test-bare").unwrap();
+    git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(source_dir.path().to_str().unwrap(), bare_dir.path())
+        .unwrap();
+
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[("GIT_DIR", bare_dir.path().to_str().unwrap())],
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v2.0.0",
+                commits_since_tag: 0,
+            }),
+            commit_id: &head_commit_id(&source_repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn ceiling_dir() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {{
+    git2version::init_proxy_build!(git2version::ProxyBuildConfig::new().ceiling_dir(".."));
+}}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.0.0");
+    // The repository is in the ceiling directory and the proxy crate is below it
+    run_version_test_project(project_dir.path(), None);
+}
+
+#[test]
+fn git_ceiling_directories_env() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.0.0");
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[(
+            "GIT_CEILING_DIRECTORIES",
+            project_dir.path().to_str().unwrap(),
+        )],
+        None,
+    );
+}
+
+#[test]
+fn require_tracked() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {{
+    git2version::init_proxy_build!(git2version::ProxyBuildConfig::new().require_tracked(true));
+}}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    // Only commit a file outside of the proxy crate
+    create_change(&repo);
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(FILENAME)).unwrap();
+    index.write().unwrap();
+    commit(&repo, &[], "Initial commit");
+    create_tag(&repo, "v1.0.0");
+    run_version_test_project(project_dir.path(), None);
+
+    // Once the proxy crate is tracked, the repository is used
+    create_file(
+        &project_dir.path().join(".gitignore"),
+        "target/\nCargo.lock\n",
+    );
+    add_all_changes_to_index(&repo);
+    let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
+    commit(&repo, &[&head_commit], "Add project");
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.0.0",
+                commits_since_tag: 1,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn no_git_with_env_override() {
+    let project_dir = make_version_test_project();
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[
+            ("GIT2VERSION_TAG", "v3.1.4"),
+            ("GIT2VERSION_COMMITS_SINCE_TAG", "2"),
+            (
+                "GIT2VERSION_COMMIT",
+                "a9ebd080a7b1c76a8b3f3080a7b1c7c76a8b3f30",
+            ),
+        ],
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v3.1.4",
+                commits_since_tag: 2,
+            }),
+            commit_id: "a9ebd080a7",
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn no_git_with_env_override_without_commit_id() {
+    // This is what `git2version read --format shell` prints for a version from a Cargo.toml
+    let project_dir = make_version_test_project();
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[
+            ("GIT2VERSION_TAG", "1.2.3"),
+            ("GIT2VERSION_COMMITS_SINCE_TAG", "0"),
+            ("GIT2VERSION_COMMIT", ""),
+            ("GIT2VERSION_MODIFIED", "false"),
+        ],
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "1.2.3",
+                commits_since_tag: 0,
+            }),
+            commit_id: "",
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn env_override_takes_precedence_over_git() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    create_change(&repo);
+    run_version_test_project_with_env(
+        project_dir.path(),
+        &[
+            ("GIT2VERSION_TAG", "v1.2.3"),
+            ("GIT2VERSION_MODIFIED", "false"),
+        ],
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3",
+                commits_since_tag: 0,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn env_override_with_special_characters() {
+    let project_dir = make_version_test_project();
+    let tag = "v1.2.3 \"quoted\" \\ ünïcödé 🦀\nsecond line";
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .env("GIT2VERSION_TAG", tag)
+            .env("GIT2VERSION_COMMIT", "a9ebd080a7")
+            .current_dir(project_dir.path()),
+    );
+    // Deserialize into the owned type because the JSON strings contain escape sequences
+    let actual_version: Option<GitInfoOwned> = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        Some(GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: tag.to_string(),
+                commits_since_tag: 0,
+            }),
+            commit_id: "a9ebd080a7".to_string(),
+            modified: false,
+            source: VersionSource::Environment,
+            worktree: None,
+        }),
+        actual_version
+    );
+}
+
+#[test]
+fn named_proxies() {
+    let server_dir = TempDir::new("package-version-test-server").unwrap();
+    let server_repo = create_repo(server_dir.path());
+    create_some_commits_and_a_tag(&server_repo, "server-v2.0.0");
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        &format!(
+            r#"
+fn main() {{
+    git2version::init_proxy_build!();
+    git2version::init_proxy_build!(
+        "server",
+        git2version::ProxyBuildConfig::new()
+            .repository_dir({:?})
+    );
+}}
+        "#,
+            server_dir.path()
+        ),
+        r#"
+fn main() {
+    println!("{}", serde_json::to_string(&(version_proxy::GITINFO, version_proxy::SERVER_GITINFO)).unwrap());
+}
+        "#,
+    );
+    create_file(
+        &project_dir.path().join("version-proxy/src/lib.rs"),
+        r#"
+            git2version::init_proxy_lib!();
+            git2version::init_proxy_lib!(SERVER_GITINFO, "server");
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.0.0");
+
+    let run = |env: &[(&str, &str)]| {
+        let output = _run_process(
+            Command::new(env!("CARGO"))
+                .arg("run")
+                .envs(env.iter().copied())
+                .current_dir(project_dir.path()),
+        );
+        serde_json::from_str::<(Option<GitInfoOwned>, Option<GitInfoOwned>)>(&output).unwrap()
+    };
+    let git_info = |tag: &str, repo: &Repository, source| GitInfoOwned {
+        tag_info: Some(TagInfoOwned {
+            tag: tag.to_string(),
+            commits_since_tag: 0,
+        }),
+        commit_id: head_commit_id(repo),
+        modified: false,
+        source,
+        worktree: None,
+    };
+
+    assert_eq!(
+        (
+            Some(git_info("v1.0.0", &repo, VersionSource::Git)),
+            Some(git_info("server-v2.0.0", &server_repo, VersionSource::Git)),
+        ),
+        run(&[])
+    );
+    // Each proxy only reads its own environment variables
+    assert_eq!(
+        (
+            Some(git_info("v1.0.0", &repo, VersionSource::Git)),
+            Some(git_info(
+                "server-v3.0.0",
+                &server_repo,
+                VersionSource::Environment
+            )),
+        ),
+        run(&[("GIT2VERSION_SERVER__TAG", "server-v3.0.0")])
+    );
+}
+
+#[test]
+fn named_proxy_with_unsupported_config_fails() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+    git2version::init_proxy_build!(
+        "server",
+        git2version::ProxyBuildConfig::new().embed_link_section(true)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.0.0");
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert!(
+        stderr.contains(
+            "ProxyBuildConfig::embed_link_section isn't supported for the named proxy 'server'"
+        ),
+        "Unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn submodules() {
+    let lib_dir = TempDir::new("package-version-test-lib").unwrap();
+    let lib_repo = create_repo(lib_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&lib_repo, "lib-v1.0.0");
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(git2version::ProxyBuildConfig::new().submodules(true));
+}
+        "#,
+        r#"
+fn main() {
+    println!("{}", serde_json::to_string(&version_proxy::SUBMODULES).unwrap());
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_initial_commit(&repo);
+    let mut submodule = repo
+        .submodule(
+            lib_dir.path().to_str().unwrap(),
+            Path::new("vendor/lib"),
+            true,
+        )
+        .unwrap();
+    submodule.clone(None).unwrap();
+    submodule.add_finalize().unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    commit(&repo, &[&head], "Add submodule");
+
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    let submodules: Vec<SubmoduleInfoOwned> = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        vec![SubmoduleInfoOwned {
+            name: "vendor/lib".to_string(),
+            path: "vendor/lib".to_string(),
+            git_info: Some(GitInfoOwned {
+                tag_info: Some(TagInfoOwned {
+                    tag: "lib-v1.0.0".to_string(),
+                    commits_since_tag: 5,
+                }),
+                commit_id: head_commit_id(&lib_repo),
+                modified: false,
+                source: VersionSource::Git,
+                worktree: None,
+            }),
+        }],
+        submodules
+    );
+}
+
+#[test]
+fn submodules_disabled() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+}
+        "#,
+        r#"
+fn main() {
+    println!("{}", version_proxy::SUBMODULES.len());
+}
+        "#,
+    );
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert_eq!("0\n", output);
+}
+
+#[test]
+fn additional_repositories() {
+    let sibling_dir = TempDir::new("package-version-test-sibling").unwrap();
+    let sibling_repo = create_repo(sibling_dir.path());
+    create_some_commits_and_a_tag(&sibling_repo, "sibling-v1.0.0");
+    let other_dir = TempDir::new("package-version-test-other").unwrap();
+    let other_repo = create_repo(other_dir.path());
+    create_some_commits_but_no_tags(&other_repo);
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        &format!(
+            r#"
+fn main() {{
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new()
+            .additional_repository("SIBLING_GITINFO", {:?})
+            .additional_repository_from_env("OTHER_GITINFO", "GIT2VERSION_TEST_OTHER_DIR")
+    );
+}}
+        "#,
+            sibling_dir.path()
+        ),
+        r#"
+fn main() {
+    println!("{}", serde_json::to_string(&(version_proxy::SIBLING_GITINFO, version_proxy::OTHER_GITINFO)).unwrap());
+}
+        "#,
+    );
+
+    let run = |env: &[(&str, &str)]| {
+        let output = _run_process(
+            Command::new(env!("CARGO"))
+                .arg("run")
+                .envs(env.iter().copied())
+                .current_dir(project_dir.path()),
+        );
+        serde_json::from_str::<(Option<GitInfoOwned>, Option<GitInfoOwned>)>(&output).unwrap()
+    };
+    let sibling_info = |commits_since_tag| GitInfoOwned {
+        tag_info: Some(TagInfoOwned {
+            tag: "sibling-v1.0.0".to_string(),
+            commits_since_tag,
+        }),
+        commit_id: head_commit_id(&sibling_repo),
+        modified: false,
+        source: VersionSource::Git,
+        worktree: None,
+    };
+
+    assert_eq!((Some(sibling_info(0)), None), run(&[]));
+
+    // The build script reruns on changes to the additional repository and its environment variable
+    create_change_and_commit(&sibling_repo);
+    assert_eq!(
+        (
+            Some(sibling_info(1)),
+            Some(GitInfoOwned {
+                tag_info: None,
+                commit_id: head_commit_id(&other_repo),
+                modified: false,
+                source: VersionSource::Git,
+                worktree: None,
+            })
+        ),
+        run(&[(
+            "GIT2VERSION_TEST_OTHER_DIR",
+            other_dir.path().to_str().unwrap()
+        )])
+    );
+}
+
+#[test]
+fn additional_repository_with_invalid_name_fails() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().additional_repository("VERSION", "..")
+    );
+}
+        "#,
+    );
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO"))
+            .arg("build")
+            .current_dir(project_dir.path()),
+    );
+    assert!(
+        stderr.contains("Constant name 'VERSION' for additional repository is already used"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn no_git_with_snapshot_file() {
+    let project_dir = make_version_test_project();
+    create_file(
+        &project_dir.path().join("version-proxy/.git2version.json"),
+        r#"
+{
+  "tag_info": {
+    "tag": "v1.2.3-alpha",
+    "commits_since_tag": 4
+  },
+  "commit_id": "a9ebd080a7",
+  "modified": false
+}
+        "#,
+    );
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3-alpha",
+                commits_since_tag: 4,
+            }),
+            commit_id: "a9ebd080a7",
+            modified: false,
+            source: VersionSource::SnapshotFile,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn snapshot_file_in_repository_dir() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {{
+    git2version::init_proxy_build!(git2version::ProxyBuildConfig::new().repository_dir("../server"));
+}}
+        "#,
+    );
+    let snapshot = r#"
+{
+  "tag_info": {
+    "tag": "v1.2.3-alpha",
+    "commits_since_tag": 4
+  },
+  "commit_id": "a9ebd080a7",
+  "modified": false
+}
+        "#;
+    // The snapshot file next to the proxy crate's Cargo.toml is ignored if `repository_dir` is set
+    create_file(
+        &project_dir.path().join("version-proxy/.git2version.json"),
+        &snapshot.replace("v1.2.3-alpha", "v0.0.1"),
+    );
+    create_file(
+        &project_dir.path().join("server/.git2version.json"),
+        snapshot,
+    );
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3-alpha",
+                commits_since_tag: 4,
+            }),
+            commit_id: "a9ebd080a7",
+            modified: false,
+            source: VersionSource::SnapshotFile,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn git_takes_precedence_over_snapshot_file() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.2.3-alpha");
+    create_file(
+        &project_dir.path().join("version-proxy/.git2version.json"),
+        r#"{"tag_info": null, "commit_id": "a9ebd080a7", "modified": false}"#,
+    );
+    add_all_changes_to_index(&repo);
+    let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
+    commit(&repo, &[&head_commit], "Add snapshot file");
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3-alpha",
+                commits_since_tag: 1,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn no_git_with_cargo_manifest_fallback() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_manifest_fallback("..")
+    );
+}
+        "#,
+    );
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "0.1.0",
+                commits_since_tag: 0,
+            }),
+            commit_id: "",
+            modified: false,
+            source: VersionSource::CargoManifest,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn git_takes_precedence_over_cargo_manifest_fallback() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_manifest_fallback("..")
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.2.3-alpha");
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v1.2.3-alpha",
+                commits_since_tag: 0,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn cargo_version_check_matching_tag() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_version_check("..", git2version::OnVersionMismatch::Fail)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v0.1.0");
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v0.1.0",
+                commits_since_tag: 0,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+#[test]
+fn cargo_version_check_mismatching_tag_fails() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_version_check("..", git2version::OnVersionMismatch::Fail)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v0.2.0");
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert!(
+        stderr.contains("HEAD is on tag 'v0.2.0' but the version in Cargo.toml is '0.1.0'"),
+        "Unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cargo_version_check_unreadable_manifest_fails_even_with_warn() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_version_check("../nonexistent", git2version::OnVersionMismatch::Warn)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v0.1.0");
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert!(
+        stderr.contains("Error getting version info from Cargo.toml for the version check"),
+        "Unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cargo_version_check_mismatching_tag_ignored_after_tag() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().cargo_version_check("..", git2version::OnVersionMismatch::Fail)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v0.2.0");
+    run_version_test_project(
+        project_dir.path(),
+        Some(GitInfo {
+            tag_info: Some(TagInfo {
+                tag: "v0.2.0",
+                commits_since_tag: 5,
+            }),
+            commit_id: &head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+    );
+}
+
+// Uses the constants in `const` context to make sure they're usable there
+const PRINT_VERSION_CONSTS_MAIN_RS: &str = r#"
+const VERSION: &str = version_proxy::VERSION;
+const LONG_VERSION: &str = version_proxy::LONG_VERSION;
+
+fn main() {
+    println!("{VERSION}\n---\n{LONG_VERSION}");
+}
+"#;
+
+const PRINT_VERSION_STRING_MAIN_RS: &str = r#"
+fn main() {
+    println!("{}", version_proxy::VERSION_STRING);
+}
+"#;
+
+#[test]
+fn version_string_default() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+}
+        "#,
+        PRINT_VERSION_STRING_MAIN_RS,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    create_change(&repo);
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert_eq!(
+        format!("v1.2.3-alpha+5.g{}.modified\n", head_commit_id(&repo)),
+        output
+    );
+}
+
+#[test]
+fn version_string_unknown() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().version_string_template("{tag}")
+    );
+}
+        "#,
+        PRINT_VERSION_STRING_MAIN_RS,
+    );
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert_eq!("unknown\n", output);
+}
+
+#[test]
+fn version_string_template() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().version_string_template(
+            "{?tag}{tag}-{distance}{/tag}-{short}{dirty:-dirty} {branch} {full}"
+        )
+    );
+}
+        "#,
+        PRINT_VERSION_STRING_MAIN_RS,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    let head = repo.head().unwrap();
+    let full_commit_id = head.peel_to_commit().unwrap().id().to_string();
+    assert_eq!(
+        format!(
+            "v1.2.3-alpha-5-{} {} {}\n",
+            head_commit_id(&repo),
+            head.shorthand().unwrap(),
+            full_commit_id,
+        ),
+        output
+    );
+}
+
+#[test]
+fn version_string_invalid_template_fails() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().version_string_template("{unknown}")
+    );
+}
+        "#,
+        PRINT_VERSION_STRING_MAIN_RS,
+    );
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert!(
+        stderr.contains("Invalid version template at position 1: unknown placeholder"),
+        "Unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn version_consts_unknown() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+}
+        "#,
+        PRINT_VERSION_CONSTS_MAIN_RS,
+    );
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert_eq!("unknown\n---\nunknown\n", output);
+}
+
+#[test]
+fn version_consts_with_tag() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+}
+        "#,
+        PRINT_VERSION_CONSTS_MAIN_RS,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    create_change(&repo);
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    let commit_id = head_commit_id(&repo);
+    assert_eq!(
+        format!(
+            "v1.2.3-alpha+5.g{commit_id}.modified\n---\nv1.2.3-alpha+5.g{commit_id}.modified\ntag: v1.2.3-alpha\ncommits since tag: 5\ncommit: {commit_id}\nmodified: true\nsource: git\n"
+        ),
+        output
+    );
+}
+
+#[test]
+fn no_std_proxy_lib() {
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!();
+}
+        "#,
+        PRINT_VERSION_CONSTS_MAIN_RS,
+    );
+    let path_to_git2version_crate = env!("CARGO_MANIFEST_DIR");
+    create_file(
+        &project_dir.path().join("version-proxy/Cargo.toml"),
+        &format!(
+            r#"
+[package]
+name = "version-proxy"
+edition = "2021"
+version = "0.0.0"
+
+[dependencies]
+git2version = {{path = "{path_to_git2version_crate}", default-features = false}}
+
+[build-dependencies]
+git2version = {{path = "{path_to_git2version_crate}", features=["build"]}}
+        "#
+        ),
+    );
+    create_file(
+        &project_dir.path().join("version-proxy/src/lib.rs"),
+        r#"
+#![no_std]
+git2version::init_proxy_lib!();
+
+// Make sure GITINFO can be formatted without std
+pub fn write_version(w: &mut impl core::fmt::Write) -> core::fmt::Result {
+    match GITINFO {
+        Some(info) => write!(w, "{info}"),
+        None => write!(w, "unknown"),
+    }
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    let commit_id = head_commit_id(&repo);
+    assert_eq!(
+        format!(
+            "v1.2.3-alpha+5.g{commit_id}\n---\nv1.2.3-alpha+5.g{commit_id}\ntag: v1.2.3-alpha\ncommits since tag: 5\ncommit: {commit_id}\nmodified: false\nsource: git\n"
+        ),
+        output
+    );
+}
+
+#[cfg(feature = "reader")]
+#[test]
+fn link_section() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().embed_link_section(true)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    create_change(&repo);
+    let binary = build_version_test_project(project_dir.path());
+    assert_eq!(
+        Some(GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3-alpha".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: head_commit_id(&repo),
+            modified: true,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+        git2version::read_link_section(&binary).unwrap()
+    );
+}
+
+#[cfg(feature = "reader")]
+#[test]
+fn link_section_unknown() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().embed_link_section(true)
+    );
+}
+        "#,
+    );
+    let binary = build_version_test_project(project_dir.path());
+    assert_eq!(None, git2version::read_link_section(&binary).unwrap());
+}
+
+#[cfg(feature = "reader")]
+#[test]
+fn link_section_disabled() {
+    let project_dir = make_version_test_project();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    let binary = build_version_test_project(project_dir.path());
+    assert!(binary.is_file());
+    assert_eq!(
+        std::io::ErrorKind::NotFound,
+        git2version::read_link_section(&binary).unwrap_err().kind()
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_read_json() {
+    let project_dir = make_version_test_project_with_build_rs(
+        r#"
+fn main() {
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().embed_link_section(true)
+    );
+}
+        "#,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    let binary = build_version_test_project(project_dir.path());
+    let output = _run_process(
+        Command::new(env!("CARGO_BIN_EXE_git2version"))
+            .args(["read", "--format", "json"])
+            .arg(&binary),
+    );
+    assert_eq!(
+        Some(GitInfoOwned {
+            tag_info: Some(TagInfoOwned {
+                tag: "v1.2.3-alpha".to_string(),
+                commits_since_tag: 5,
+            }),
+            commit_id: head_commit_id(&repo),
+            modified: false,
+            source: VersionSource::Git,
+            worktree: None,
+        }),
+        serde_json::from_str::<Option<GitInfoOwned>>(&output).unwrap()
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_read_without_link_section() {
+    let project_dir = make_version_test_project();
+    let binary = build_version_test_project(project_dir.path());
+    let stderr = _run_process_expect_failure(
+        Command::new(env!("CARGO_BIN_EXE_git2version"))
+            .arg("read")
+            .arg(&binary),
+    );
+    assert!(
+        stderr.contains("doesn't have a .git2version section"),
+        "{stderr}"
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_describe_matches_version_string() {
+    const TEMPLATE: &str = "{?tag}{tag}-{distance}{/tag}-{short}{dirty:-dirty} {branch} {full}";
+    let project_dir = make_version_test_project_with_build_rs_and_main_rs(
+        &format!(
+            r#"
+fn main() {{
+    git2version::init_proxy_build!(
+        git2version::ProxyBuildConfig::new().version_string_template({TEMPLATE:?})
+    );
+}}
+        "#
+        ),
+        PRINT_VERSION_STRING_MAIN_RS,
+    );
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    create_change(&repo);
+    let expected = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    let output = _run_process(
+        Command::new(env!("CARGO_BIN_EXE_git2version"))
+            .args(["describe", "--version-string-template", TEMPLATE])
+            .arg(project_dir.path()),
+    );
+    assert_eq!(expected, output);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_describe_env() {
+    let project_dir = TempDir::new("git2version-cli-test").unwrap();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_a_tag_and_some_more_commits(&repo, "v1.2.3-alpha");
+    let output = _run_process(
+        Command::new(env!("CARGO_BIN_EXE_git2version"))
+            .args(["describe", "--format", "env"])
+            .arg(project_dir.path()),
+    );
+    let commit_id = head_commit_id(&repo);
+    assert_eq!(
+        format!(
+            "VERSION='v1.2.3-alpha+5.g{commit_id}'\nVERSION_STRING='v1.2.3-alpha+5.g{commit_id}'\nIS_KNOWN='true'\nTAG='v1.2.3-alpha'\nCOMMITS_SINCE_TAG='5'\nCOMMIT_ID='{commit_id}'\nMODIFIED='false'\nSOURCE='git'\nWORKTREE=''\n"
+        ),
+        output
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_describe_cargo_manifest_fallback() {
+    let project_dir = TempDir::new("git2version-cli-test").unwrap();
+    std::fs::write(
+        project_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"some-crate\"\nversion = \"2.3.4\"\n",
+    )
+    .unwrap();
+    let output = _run_process(
+        Command::new(env!("CARGO_BIN_EXE_git2version"))
+            .args(["describe", "--cargo-manifest-fallback", "."])
+            .arg(project_dir.path()),
+    );
+    assert_eq!("2.3.4+0.cargo-manifest\n", output);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_describe_discovery_ceiling() {
+    let project_dir = TempDir::new("git2version-cli-test").unwrap();
+    let repo = create_repo(project_dir.path());
+    create_some_commits_and_a_tag(&repo, "v1.0.0");
+    let crate_dir = project_dir.path().join("vendored");
+    create_file(&crate_dir.join("Cargo.toml"), "");
+    let describe = |args: &[&str]| {
+        _run_process(
+            Command::new(env!("CARGO_BIN_EXE_git2version"))
+                .arg("describe")
+                .args(args)
+                .arg(&crate_dir),
+        )
+    };
+    assert_eq!(
+        format!("v1.0.0+0.g{}\n", head_commit_id(&repo)),
+        describe(&[])
+    );
+    assert_eq!("unknown\n", describe(&["--ceiling-dir", ".."]));
+    assert_eq!("unknown\n", describe(&["--require-tracked"]));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_init() {
+    let project_dir = TempDir::new("git2version-cli-test").unwrap();
+    create_file(
+        &project_dir.path().join("Cargo.toml"),
+        &format!(
+            r#"
+[package]
+name = "package-version-test"
+edition = "2021"
+version = "0.1.0"
+
+[patch.crates-io]
+git2version = {{path = "{}"}}
+            "#,
+            env!("CARGO_MANIFEST_DIR")
+        ),
+    );
+    create_file(
+        &project_dir.path().join("src/main.rs"),
+        r#"
+fn main() {
+    println!("{}", version_proxy::VERSION);
+}
+        "#,
+    );
+    create_file(
+        &project_dir.path().join(".gitignore"),
+        "target/\nCargo.lock\n",
+    );
+    for _ in 0..2 {
+        // Running it a second time must not break anything
+        _run_process(
+            Command::new(env!("CARGO_BIN_EXE_git2version"))
+                .arg("init")
+                .current_dir(project_dir.path()),
+        );
+    }
+    let repo = create_repo(project_dir.path());
+    add_all_changes_to_index(&repo);
+    commit(&repo, &[], "Initial commit");
+    create_tag(&repo, "v1.0.0");
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .arg("run")
+            .current_dir(project_dir.path()),
+    );
+    assert_eq!(format!("v1.0.0+0.g{}\n", head_commit_id(&repo)), output);
+}
+
+/// Builds the test project and returns the path of its executable
+#[cfg(feature = "reader")]
+fn build_version_test_project(project_dir: &Path) -> std::path::PathBuf {
+    let output = _run_process(
+        Command::new(env!("CARGO"))
+            .args(["build", "--message-format=json-render-diagnostics"])
+            .current_dir(project_dir),
+    );
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["target"]["name"] == "package-version-test")
+        .find_map(|message| message["executable"].as_str().map(std::path::PathBuf::from))
+        .expect("Didn't find executable in cargo output")
+}
+
 fn head_commit_id(repo: &Repository) -> String {
     let head = repo.head().unwrap().peel_to_commit().unwrap();
     let commit_id = head.id().to_string();
@@ -519,6 +1887,27 @@ fn head_commit_id(repo: &Repository) -> String {
 }
 
 fn make_version_test_project() -> TempDir {
+    make_version_test_project_with_build_rs(
+        r#"
+fn main() {{
+    git2version::init_proxy_build!();
+}}
+        "#,
+    )
+}
+
+fn make_version_test_project_with_build_rs(build_rs: &str) -> TempDir {
+    make_version_test_project_with_build_rs_and_main_rs(
+        build_rs,
+        r#"
+fn main() {
+    println!("{}", serde_json::to_string(&version_proxy::GITINFO).unwrap());
+}
+        "#,
+    )
+}
+
+fn make_version_test_project_with_build_rs_and_main_rs(build_rs: &str, main_rs: &str) -> TempDir {
     let dir = TempDir::new("package-version-test").unwrap();
     let dir_path = dir.path();
     let path_to_git2version_crate = env!("CARGO_MANIFEST_DIR");
@@ -540,14 +1929,7 @@ serde_json = "^1.0.96"
         "#,
     );
 
-    create_file(
-        &dir_path.join("src/main.rs"),
-        r#"
-fn main() {
-    println!("{}", serde_json::to_string(&version_proxy::GITINFO).unwrap());
-}
-        "#,
-    );
+    create_file(&dir_path.join("src/main.rs"), main_rs);
 
     create_file(
         &dir_path.join("version-proxy/Cargo.toml"),
@@ -560,7 +1942,7 @@ edition = "2021"
 version = "0.0.0"
 
 [dependencies]
-git2version = {{path = "{path_to_git2version_crate}"}}
+git2version = {{path = "{path_to_git2version_crate}", features=["serde"]}}
 
 [build-dependencies]
 git2version = {{path = "{path_to_git2version_crate}", features=["build"]}}
@@ -568,14 +1950,7 @@ git2version = {{path = "{path_to_git2version_crate}", features=["build"]}}
         ),
     );
 
-    create_file(
-        &dir_path.join("version-proxy/build.rs"),
-        r#"
-fn main() {{
-    git2version::init_proxy_build!();
-}}
-        "#,
-    );
+    create_file(&dir_path.join("version-proxy/build.rs"), build_rs);
 
     create_file(
         &dir_path.join("version-proxy/src/lib.rs"),
@@ -596,9 +1971,18 @@ fn create_file(path: &Path, content: &str) {
 }
 
 fn run_version_test_project(project_dir: &Path, expected_version: Option<GitInfo>) {
+    run_version_test_project_with_env(project_dir, &[], expected_version);
+}
+
+fn run_version_test_project_with_env(
+    project_dir: &Path,
+    env: &[(&str, &str)],
+    expected_version: Option<GitInfo>,
+) {
     let output = _run_process(
         Command::new(env!("CARGO"))
             .arg("run")
+            .envs(env.iter().copied())
             .current_dir(project_dir),
     );
 
@@ -619,3 +2003,16 @@ fn _run_process(cmd: &mut Command) -> String {
     }
     String::from_utf8_lossy(&output.stdout).to_string()
 }
+
+fn _run_process_expect_failure(cmd: &mut Command) -> String {
+    let output = cmd.output().unwrap();
+    if output.status.success() {
+        panic!(
+            "Command {:?} succeeded but was expected to fail. stdout:\n{}\n\nstderr:\n{}",
+            cmd,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    String::from_utf8_lossy(&output.stderr).to_string()
+}